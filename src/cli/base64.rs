@@ -1,7 +1,14 @@
 use core::fmt;
-use std::str::FromStr;
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    str::FromStr,
+};
 
-use crate::CmdExector;
+use crate::{
+    utils::{get_writer, write_atomic, write_stdout},
+    CmdExector,
+};
 
 use super::verify_file;
 use clap::Parser;
@@ -17,18 +24,42 @@ pub enum Base64SubCommand {
 }
 impl CmdExector for Base64EncodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let encode_data = crate::process_encode(&self.input, self.format)?;
-        println!("{}", encode_data);
+        let mut writer = get_writer(self.output.as_deref())?;
+        if self.data_uri {
+            crate::process_encode_data_uri(&self.input, &mut writer)?;
+        } else {
+            crate::process_encode(&self.input, &mut writer, self.format)?;
+        }
+        if self.output.is_none() {
+            writeln!(writer)?;
+        }
+        writer.flush()?;
         Ok(())
     }
 }
 
 impl CmdExector for Base64DecodeOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let decode_data = crate::process_decode(&self.input, self.format)?;
-        // TODO: decoded data might not be string
-        let decode_data = String::from_utf8(decode_data)?;
-        println!("{}", decode_data);
+        if self.data_uri {
+            let (mime, data) = crate::process_decode_data_uri(&self.input)?;
+            let output = match self.output {
+                Some(output) if output.extension().is_some() => output,
+                Some(output) => output.with_extension(crate::mime_extension(&mime)),
+                None => PathBuf::from(format!("output.{}", crate::mime_extension(&mime))),
+            };
+            write_atomic(&output, &data, self.force, 0o644)?;
+            eprintln!("{} written to {}", mime, output.display());
+            return Ok(());
+        }
+
+        if self.output.is_none() && io::stdout().is_terminal() {
+            let mut reader = crate::process_decode_reader(&self.input, self.format, self.lenient)?;
+            return write_stdout(&mut reader);
+        }
+
+        let mut writer = get_writer(self.output.as_deref())?;
+        crate::process_decode(&self.input, &mut writer, self.format, self.lenient)?;
+        writer.flush()?;
         Ok(())
     }
 }
@@ -37,8 +68,17 @@ impl CmdExector for Base64DecodeOpts {
 pub struct Base64EncodeOpts {
     #[arg(short,long,value_parser=verify_file,default_value="-")]
     pub input: String,
+
+    /// Write the encoded output to this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
     #[arg(long,default_value="standard",value_parser=parse_format)]
     pub format: Base64Format,
+
+    /// Produce a data:<mime>;base64,... URI, with the type sniffed from the content
+    #[arg(long, default_value_t = false, conflicts_with = "format")]
+    pub data_uri: bool,
 }
 
 #[derive(Debug, Parser)]
@@ -46,14 +86,35 @@ pub struct Base64DecodeOpts {
     #[arg(short,long,value_parser=verify_file,default_value="-")]
     pub input: String,
 
+    /// Write the decoded bytes to this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
     #[arg(long,default_value="standard",value_parser=parse_format)]
     pub format: Base64Format,
+
+    /// Accept either alphabet, missing padding and whitespace, ignoring --format
+    #[arg(long, default_value_t = false)]
+    pub lenient: bool,
+
+    /// Parse a data URI and save the payload with a matching file extension,
+    /// to output.<ext> unless -o is given
+    #[arg(long, default_value_t = false, conflicts_with_all = ["format", "lenient"])]
+    pub data_uri: bool,
+
+    /// Overwrite the data URI payload file if it already exists
+    #[arg(long, default_value_t = false, requires = "data_uri")]
+    pub force: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
 pub enum Base64Format {
     Standard,
+    StandardNoPad,
     UrlSafe,
+    UrlSafeNoPad,
+    /// Standard alphabet wrapped at 76 columns
+    Mime,
 }
 
 fn parse_format(format: &str) -> Result<Base64Format, anyhow::Error> {
@@ -66,7 +127,10 @@ impl FromStr for Base64Format {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "standard" => Ok(Base64Format::Standard),
+            "standard-nopad" => Ok(Base64Format::StandardNoPad),
             "urlsafe" => Ok(Base64Format::UrlSafe),
+            "urlsafe-nopad" => Ok(Base64Format::UrlSafeNoPad),
+            "mime" => Ok(Base64Format::Mime),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
@@ -76,7 +140,10 @@ impl From<Base64Format> for &'static str {
     fn from(value: Base64Format) -> Self {
         match value {
             Base64Format::Standard => "standard",
+            Base64Format::StandardNoPad => "standard-nopad",
             Base64Format::UrlSafe => "urlsafe",
+            Base64Format::UrlSafeNoPad => "urlsafe-nopad",
+            Base64Format::Mime => "mime",
         }
     }
 }
@@ -0,0 +1,130 @@
+use core::fmt;
+use std::{path::PathBuf, str::FromStr};
+
+use clap::Parser;
+
+use crate::{utils::write_output, CmdExector};
+
+use super::{verify_file, Base64Format};
+
+#[derive(Debug, Parser)]
+pub struct EncodeOpts {
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    #[arg(long, value_parser=parse_encoding)]
+    pub to: Encoding,
+}
+
+#[derive(Debug, Parser)]
+pub struct DecodeOpts {
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Write the decoded bytes to this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[arg(long, value_parser=parse_encoding, required_unless_present = "auto")]
+    pub from: Option<Encoding>,
+
+    /// Detect the encoding and peel nested layers (base64, hex, base32, JWT, gzip, URL)
+    #[arg(long, default_value_t = false, conflicts_with = "from")]
+    pub auto: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Encoding {
+    Base64(Base64Format),
+    Hex,
+    HexUpper,
+    /// RFC 4648
+    Base32,
+    Base32Crockford,
+    /// Bitcoin alphabet
+    Base58,
+    Ascii85,
+    Z85,
+    /// URL percent-encoding
+    Percent,
+}
+
+impl CmdExector for EncodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let encoded = crate::process_codec_encode(&self.input, self.to)?;
+        println!("{}", encoded);
+        Ok(())
+    }
+}
+
+impl CmdExector for DecodeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let decoded = match self.from {
+            Some(from) => crate::process_codec_decode(&self.input, from)?,
+            None => {
+                let (chain, decoded) = crate::process_auto_decode(&self.input)?;
+                if chain.is_empty() {
+                    eprintln!("detected: no known encoding");
+                } else {
+                    eprintln!("detected: {}", chain.join(" -> "));
+                }
+                decoded
+            }
+        };
+        write_output(&decoded, self.output.as_deref())?;
+        Ok(())
+    }
+}
+
+fn parse_encoding(encoding: &str) -> Result<Encoding, anyhow::Error> {
+    encoding.parse()
+}
+
+impl FromStr for Encoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(Encoding::Base64(Base64Format::Standard)),
+            "base64-nopad" => Ok(Encoding::Base64(Base64Format::StandardNoPad)),
+            "base64url" => Ok(Encoding::Base64(Base64Format::UrlSafe)),
+            "base64url-nopad" => Ok(Encoding::Base64(Base64Format::UrlSafeNoPad)),
+            "base64-mime" => Ok(Encoding::Base64(Base64Format::Mime)),
+            "hex" => Ok(Encoding::Hex),
+            "hex-upper" => Ok(Encoding::HexUpper),
+            "base32" => Ok(Encoding::Base32),
+            "base32-crockford" => Ok(Encoding::Base32Crockford),
+            "base58" => Ok(Encoding::Base58),
+            "ascii85" => Ok(Encoding::Ascii85),
+            "z85" => Ok(Encoding::Z85),
+            "percent" | "url" => Ok(Encoding::Percent),
+            v => anyhow::bail!("Unsupported encoding: {}", v),
+        }
+    }
+}
+
+impl From<Encoding> for &'static str {
+    fn from(value: Encoding) -> Self {
+        match value {
+            Encoding::Base64(Base64Format::Standard) => "base64",
+            Encoding::Base64(Base64Format::StandardNoPad) => "base64-nopad",
+            Encoding::Base64(Base64Format::UrlSafe) => "base64url",
+            Encoding::Base64(Base64Format::UrlSafeNoPad) => "base64url-nopad",
+            Encoding::Base64(Base64Format::Mime) => "base64-mime",
+            Encoding::Hex => "hex",
+            Encoding::HexUpper => "hex-upper",
+            Encoding::Base32 => "base32",
+            Encoding::Base32Crockford => "base32-crockford",
+            Encoding::Base58 => "base58",
+            Encoding::Ascii85 => "ascii85",
+            Encoding::Z85 => "z85",
+            Encoding::Percent => "percent",
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -0,0 +1,133 @@
+use core::fmt;
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use clap::Parser;
+
+use crate::{
+    utils::{get_writer, write_stdout},
+    CmdExector,
+};
+
+use super::{verify_file, verify_path};
+
+#[derive(Debug, Parser)]
+pub struct CompressOpts {
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Write the compressed data to this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    #[arg(short, long, default_value="gzip", value_parser=parse_compress_algo)]
+    pub algo: CompressAlgo,
+
+    /// Compression level, the algorithm's default when omitted
+    #[arg(short, long)]
+    pub level: Option<u32>,
+
+    /// Write .gz, .br, .zst and .zz siblings for every file under this
+    /// directory, the variants `http serve` looks for
+    #[arg(long, value_parser=verify_path, conflicts_with_all = ["input", "output", "algo"])]
+    pub precompress_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Parser)]
+pub struct DecompressOpts {
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Write the decompressed data to this file
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Detected from the leading bytes when omitted
+    #[arg(short, long, value_parser=parse_compress_algo)]
+    pub algo: Option<CompressAlgo>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompressAlgo {
+    Gzip,
+    Zstd,
+    Brotli,
+    /// zlib-wrapped deflate, as HTTP `Content-Encoding: deflate` means
+    Deflate,
+    Xz,
+}
+
+impl CmdExector for CompressOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if let Some(dir) = self.precompress_dir {
+            let stats = crate::process_precompress_dir(&dir, self.level)?;
+            eprintln!(
+                "{} file(s) scanned, {} variant(s) written, {} skipped as not smaller",
+                stats.files, stats.written, stats.skipped
+            );
+            return Ok(());
+        }
+
+        if self.output.is_none() && io::stdout().is_terminal() {
+            anyhow::bail!("Compressed data not written to a terminal, use -o or a pipe");
+        }
+        let mut writer = get_writer(self.output.as_deref())?;
+        crate::process_compress(&self.input, &mut writer, self.algo, self.level)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl CmdExector for DecompressOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if self.output.is_none() && io::stdout().is_terminal() {
+            let mut reader = crate::process_decompress_reader(&self.input, self.algo)?;
+            return write_stdout(&mut reader);
+        }
+
+        let mut writer = get_writer(self.output.as_deref())?;
+        crate::process_decompress(&self.input, &mut writer, self.algo)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn parse_compress_algo(algo: &str) -> Result<CompressAlgo, anyhow::Error> {
+    algo.parse()
+}
+
+impl FromStr for CompressAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" | "gz" => Ok(CompressAlgo::Gzip),
+            "zstd" | "zst" => Ok(CompressAlgo::Zstd),
+            "brotli" | "br" => Ok(CompressAlgo::Brotli),
+            "deflate" => Ok(CompressAlgo::Deflate),
+            "xz" => Ok(CompressAlgo::Xz),
+            v => anyhow::bail!("Unsupported compression algorithm: {}", v),
+        }
+    }
+}
+
+impl From<CompressAlgo> for &'static str {
+    fn from(value: CompressAlgo) -> Self {
+        match value {
+            CompressAlgo::Gzip => "gzip",
+            CompressAlgo::Zstd => "zstd",
+            CompressAlgo::Brotli => "brotli",
+            CompressAlgo::Deflate => "deflate",
+            CompressAlgo::Xz => "xz",
+        }
+    }
+}
+
+impl fmt::Display for CompressAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
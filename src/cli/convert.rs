@@ -0,0 +1,53 @@
+use std::{fs, path::PathBuf};
+
+use clap::Parser;
+
+use crate::CmdExector;
+
+use super::{verify_file, OutputFormat};
+
+#[derive(Debug, Parser)]
+pub struct ConvertOpts {
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Write the converted document to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Input format, taken from the file extension when omitted
+    #[arg(long, value_parser=parse_format)]
+    pub from: Option<OutputFormat>,
+
+    #[arg(long, value_parser=parse_format)]
+    pub to: OutputFormat,
+
+    /// Single-line JSON, inline TOML arrays
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+
+    /// Sort object keys recursively instead of keeping the source order
+    #[arg(long, default_value_t = false)]
+    pub sort_keys: bool,
+}
+
+impl CmdExector for ConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let content = crate::process_convert(
+            &self.input,
+            self.from,
+            self.to,
+            self.compact,
+            self.sort_keys,
+        )?;
+        match self.output {
+            Some(output) => fs::write(output, content)?,
+            None => print!("{}", content),
+        }
+        Ok(())
+    }
+}
+
+fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
+    format.parse()
+}
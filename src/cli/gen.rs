@@ -0,0 +1,142 @@
+use core::fmt;
+use std::str::FromStr;
+
+use clap::Parser;
+use enum_dispatch::enum_dispatch;
+
+use crate::CmdExector;
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum GenSubCommand {
+    #[command(about = "Generate a random token")]
+    Token(GenTokenOpts),
+
+    #[command(about = "Generate a random UUID")]
+    Uuid(GenUuidOpts),
+
+    #[command(about = "Generate a ULID")]
+    Ulid(GenUlidOpts),
+
+    #[command(about = "Generate a nanoid")]
+    Nanoid(GenNanoidOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenTokenOpts {
+    /// Number of random bytes
+    #[arg(short, long, default_value_t = 32)]
+    pub bytes: usize,
+
+    #[arg(long, default_value="hex", value_parser=parse_token_encoding)]
+    pub encoding: TokenEncoding,
+}
+
+#[derive(Debug, Parser)]
+pub struct GenUuidOpts {
+    /// Random UUID (default)
+    #[arg(long, default_value_t = false, conflicts_with = "v7")]
+    pub v4: bool,
+
+    /// Time-ordered UUID
+    #[arg(long, default_value_t = false)]
+    pub v7: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct GenUlidOpts {}
+
+#[derive(Debug, Parser)]
+pub struct GenNanoidOpts {
+    #[arg(short, long, default_value_t = 21)]
+    pub size: usize,
+
+    #[arg(short, long, default_value = crate::NANOID_ALPHABET)]
+    pub alphabet: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TokenEncoding {
+    Hex,
+    Base64Url,
+    Base58,
+    Base32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UuidVersion {
+    V4,
+    V7,
+}
+
+impl CmdExector for GenTokenOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let token = crate::process_gen_token(self.bytes, self.encoding)?;
+        println!("{}", token);
+        Ok(())
+    }
+}
+
+impl CmdExector for GenUuidOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let version = if self.v7 {
+            UuidVersion::V7
+        } else {
+            UuidVersion::V4
+        };
+        let uuid = crate::process_gen_uuid(version)?;
+        println!("{}", uuid);
+        Ok(())
+    }
+}
+
+impl CmdExector for GenUlidOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let ulid = crate::process_gen_ulid()?;
+        println!("{}", ulid);
+        Ok(())
+    }
+}
+
+impl CmdExector for GenNanoidOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let id = crate::process_gen_nanoid(self.size, &self.alphabet)?;
+        println!("{}", id);
+        Ok(())
+    }
+}
+
+fn parse_token_encoding(encoding: &str) -> Result<TokenEncoding, anyhow::Error> {
+    encoding.parse()
+}
+
+impl FromStr for TokenEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hex" => Ok(TokenEncoding::Hex),
+            "base64url" => Ok(TokenEncoding::Base64Url),
+            "base58" => Ok(TokenEncoding::Base58),
+            "base32" => Ok(TokenEncoding::Base32),
+            v => anyhow::bail!("Unsupported encoding: {}", v),
+        }
+    }
+}
+
+impl From<TokenEncoding> for &'static str {
+    fn from(value: TokenEncoding) -> Self {
+        match value {
+            TokenEncoding::Hex => "hex",
+            TokenEncoding::Base64Url => "base64url",
+            TokenEncoding::Base58 => "base58",
+            TokenEncoding::Base32 => "base32",
+        }
+    }
+}
+
+impl fmt::Display for TokenEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
@@ -1,11 +1,21 @@
+use core::fmt;
+use std::{path::Path, str::FromStr};
+
 use anyhow::Ok;
 use clap::Parser;
+use enum_dispatch::enum_dispatch;
 use zxcvbn::zxcvbn;
 
 use crate::CmdExector;
 
+use super::{verify_file, SecretOutputOpts};
+
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct GenPassOpts {
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+
     #[arg(short, long, default_value_t = 16)]
     pub length: u8,
 
@@ -20,21 +30,171 @@ pub struct GenPassOpts {
 
     #[arg(long, default_value_t = false)]
     pub no_symbol: bool,
+
+    /// Template: c/C consonant, v/V vowel, 9 digit, s symbol, others are literal
+    #[arg(long, conflicts_with_all = ["length", "no_uppercase", "no_lowercase", "no_number", "no_symbol"])]
+    pub pattern: Option<String>,
+
+    /// Shortcut for a pronounceable pattern (Cvccvc-Cvccvc-99)
+    #[arg(long, default_value_t = false, conflicts_with_all = ["pattern", "length", "no_uppercase", "no_lowercase", "no_number", "no_symbol"])]
+    pub pronounceable: bool,
+
+    #[command(flatten)]
+    pub secret: SecretOutputOpts,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum GenPassSubCommand {
+    #[command(about = "Audit the strength of passwords, one per line")]
+    Check(GenPassCheckOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    /// Input file path, one password per line
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Words the passwords should not be based on, e.g. name,email
+    #[arg(long, value_delimiter = ',')]
+    pub user_inputs: Vec<String>,
+
+    /// Report format
+    #[arg(long, default_value="table", value_parser=parse_report_format)]
+    pub format: ReportFormat,
+
+    /// Sorted Have I Been Pwned SHA-1 dump to check passwords against, offline
+    #[arg(long, value_parser=verify_file)]
+    pub breach_db: Option<String>,
+
+    /// Exit with an error if any password scores below this (0-4)
+    #[arg(long, default_value_t = 3, value_parser=clap::value_parser!(u8).range(0..=4))]
+    pub min_score: u8,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReportFormat {
+    Table,
+    Json,
 }
 
 impl CmdExector for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let password = crate::process_genpass(
-            self.length,
-            self.no_uppercase,
-            self.no_lowercase,
-            self.no_number,
-            self.no_symbol,
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+
+        let password = if self.pronounceable {
+            crate::process_genpass_pattern(crate::PRONOUNCEABLE_PATTERN)?
+        } else if let Some(pattern) = &self.pattern {
+            crate::process_genpass_pattern(pattern)?
+        } else {
+            crate::process_genpass(
+                self.length,
+                self.no_uppercase,
+                self.no_lowercase,
+                self.no_number,
+                self.no_symbol,
+            )?
+        };
+        if self.secret.verbose() {
+            let estimate = zxcvbn(&password, &[])?;
+            eprintln!("\nPassword strength: {}", estimate.score());
+        }
+        self.secret.write(password.as_bytes())?;
+
+        Ok(())
+    }
+}
+
+impl CmdExector for GenPassCheckOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let reports = crate::process_genpass_check(
+            &self.input,
+            &self.user_inputs,
+            self.breach_db.as_deref().map(Path::new),
         )?;
-        let estimate = zxcvbn(&password, &[])?;
-        eprintln!("\nPassword strength: {}", estimate.score());
-        print!("{}", password);
 
+        match self.format {
+            ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&reports)?),
+            ReportFormat::Table => {
+                println!(
+                    "{:<5}  {:<20}  {:<20}  PASSWORD",
+                    "SCORE", "ONLINE (THROTTLED)", "OFFLINE (FAST HASH)"
+                );
+                for r in &reports {
+                    println!(
+                        "{:<5}  {:<20}  {:<20}  {}",
+                        r.score,
+                        r.crack_times.online_throttling,
+                        r.crack_times.offline_fast_hashing,
+                        r.password
+                    );
+                    if let Some(warning) = &r.warning {
+                        println!("       warning: {}", warning);
+                    }
+                    for suggestion in &r.suggestions {
+                        println!("       suggestion: {}", suggestion);
+                    }
+                    if let Some(count @ 1..) = r.breach_count {
+                        println!("       breached: seen {} times", count);
+                    }
+                }
+            }
+        }
+
+        let weak = reports.iter().filter(|r| r.score < self.min_score).count();
+        if weak > 0 {
+            anyhow::bail!(
+                "{} of {} password(s) scored below {}",
+                weak,
+                reports.len(),
+                self.min_score
+            );
+        }
+        let breached = reports
+            .iter()
+            .filter(|r| r.breach_count.unwrap_or(0) > 0)
+            .count();
+        if breached > 0 {
+            anyhow::bail!(
+                "{} of {} password(s) found in the breach database",
+                breached,
+                reports.len()
+            );
+        }
         Ok(())
     }
 }
+
+fn parse_report_format(format: &str) -> Result<ReportFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            v => anyhow::bail!("Unsupported format: {}", v),
+        }
+    }
+}
+
+impl From<ReportFormat> for &'static str {
+    fn from(value: ReportFormat) -> Self {
+        match value {
+            ReportFormat::Table => "table",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
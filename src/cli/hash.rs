@@ -0,0 +1,107 @@
+use core::fmt;
+use std::str::FromStr;
+
+use clap::Parser;
+
+use crate::{CheckStatus, CmdExector};
+
+use super::verify_file;
+
+#[derive(Debug, Parser)]
+pub struct HashOpts {
+    /// Files or directories to hash, `-` for stdin
+    #[arg(value_parser=verify_file, default_value = "-")]
+    pub inputs: Vec<String>,
+
+    #[arg(short, long, default_value="sha256", value_parser=parse_hash_algo)]
+    pub algo: HashAlgo,
+
+    /// Verify the checksums listed in this file instead
+    #[arg(short, long, value_parser=verify_file, conflicts_with = "inputs")]
+    pub check: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum HashAlgo {
+    Blake3,
+    Sha256,
+    Sha512,
+    Sha1,
+    Md5,
+    Xxh3,
+}
+
+impl CmdExector for HashOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let Some(sums) = self.check else {
+            for (path, digest) in crate::process_hash(&self.inputs, self.algo)? {
+                println!("{}  {}", digest, path);
+            }
+            return Ok(());
+        };
+
+        let results = crate::process_hash_check(&sums, self.algo)?;
+        let mut failed = 0;
+        let mut missing = 0;
+        for (path, status) in &results {
+            match status {
+                CheckStatus::Ok => println!("{}: OK", path),
+                CheckStatus::Failed => {
+                    failed += 1;
+                    println!("{}: FAILED", path);
+                }
+                CheckStatus::Missing => {
+                    missing += 1;
+                    println!("{}: FAILED open or read", path);
+                }
+            }
+        }
+        if failed + missing > 0 {
+            anyhow::bail!(
+                "{} computed checksum(s) did NOT match, {} file(s) could not be read",
+                failed,
+                missing
+            );
+        }
+        Ok(())
+    }
+}
+
+fn parse_hash_algo(algo: &str) -> Result<HashAlgo, anyhow::Error> {
+    algo.parse()
+}
+
+impl FromStr for HashAlgo {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blake3" => Ok(HashAlgo::Blake3),
+            "sha256" => Ok(HashAlgo::Sha256),
+            "sha512" => Ok(HashAlgo::Sha512),
+            "sha1" => Ok(HashAlgo::Sha1),
+            "md5" => Ok(HashAlgo::Md5),
+            "xxh3" => Ok(HashAlgo::Xxh3),
+            v => anyhow::bail!("Unsupported hash algorithm: {}", v),
+        }
+    }
+}
+
+impl From<HashAlgo> for &'static str {
+    fn from(value: HashAlgo) -> Self {
+        match value {
+            HashAlgo::Blake3 => "blake3",
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Sha512 => "sha512",
+            HashAlgo::Sha1 => "sha1",
+            HashAlgo::Md5 => "md5",
+            HashAlgo::Xxh3 => "xxh3",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
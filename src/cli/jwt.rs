@@ -6,7 +6,7 @@ use enum_dispatch::enum_dispatch;
 
 use crate::CmdExector;
 
-use super::verify_file;
+use super::{verify_file, SecretOutputOpts};
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExector)]
@@ -35,6 +35,9 @@ pub struct JwtSignOpts {
 
     #[arg(long,value_parser=parse_exp_time,default_value="14D")]
     pub exp: ExpObj,
+
+    #[command(flatten)]
+    pub token: SecretOutputOpts,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -105,8 +108,10 @@ impl CmdExector for JwtSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
         let serialized =
             crate::process_jwt_sign(&self.input, &self.secret, &self.sub, &self.aud, self.exp)?;
-        eprint!("\nserialized: ");
-        print!("{}", serialized);
+        if self.token.verbose() && self.token.output.is_none() {
+            eprint!("\nserialized: ");
+        }
+        self.token.write(serialized.as_bytes())?;
         Ok(())
     }
 }
@@ -0,0 +1,175 @@
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use clap::Parser;
+use data_encoding::HEXLOWER;
+use enum_dispatch::enum_dispatch;
+
+use crate::{
+    utils::{write_atomic, write_output},
+    CmdExector, KeyEncoding, PassphraseSource, TextSignFormat,
+};
+
+use super::{
+    text::{parse_key_encoding, parse_verify_format},
+    verify_file, PassphraseOpts, SecretOutputOpts,
+};
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum KeySubCommand {
+    #[command(about = "Show the algorithm, fingerprint and randomart of a key file")]
+    Info(KeyInfoOpts),
+
+    #[command(about = "Derive the public key of a private key file")]
+    Pub(KeyPubOpts),
+
+    #[command(about = "Store a key file in another encoding")]
+    Convert(KeyConvertOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyInfoOpts {
+    #[arg(value_parser=verify_file)]
+    pub key: String,
+
+    /// The key algorithm, needed for raw keys only
+    #[arg(long, value_parser=parse_verify_format)]
+    pub format: Option<TextSignFormat>,
+
+    #[command(flatten)]
+    pub role: KeyRoleOpts,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyPubOpts {
+    #[arg(value_parser=verify_file)]
+    pub key: String,
+
+    /// The key algorithm, needed for raw keys only
+    #[arg(long, value_parser=parse_verify_format)]
+    pub format: Option<TextSignFormat>,
+
+    #[command(flatten)]
+    pub role: KeyRoleOpts,
+
+    /// How the public key is stored [default: as the private key is]
+    #[arg(long, value_parser=parse_key_encoding)]
+    pub encoding: Option<KeyEncoding>,
+
+    /// Write the public key to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long, default_value_t = false, requires = "output")]
+    pub force: bool,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct KeyConvertOpts {
+    #[arg(value_parser=verify_file)]
+    pub key: String,
+
+    /// The key algorithm, needed for raw keys only
+    #[arg(long, value_parser=parse_verify_format)]
+    pub format: Option<TextSignFormat>,
+
+    #[command(flatten)]
+    pub role: KeyRoleOpts,
+
+    /// The encoding to store the key in, an encrypted key stays encrypted
+    #[arg(long, value_parser=parse_key_encoding)]
+    pub to: KeyEncoding,
+
+    #[command(flatten)]
+    pub output: SecretOutputOpts,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+/// Raw ed25519 private and public keys are both 32 bytes, so one of these must say which
+#[derive(Debug, Parser)]
+pub struct KeyRoleOpts {
+    /// Read a raw ed25519 key as a private key
+    #[arg(long, conflicts_with = "public")]
+    pub private: bool,
+
+    /// Read a raw ed25519 key as a public key
+    #[arg(long)]
+    pub public: bool,
+}
+
+impl KeyRoleOpts {
+    fn public(&self) -> Option<bool> {
+        match (self.private, self.public) {
+            (false, false) => None,
+            (_, public) => Some(public),
+        }
+    }
+}
+
+impl CmdExector for KeyInfoOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let info =
+            crate::process_key_info(&self.key, self.format, self.role.public(), &passphrase)?;
+        println!("algorithm: {}", info.algorithm);
+        println!("type: {} key ({} bits)", info.kind, info.bits);
+        println!("encoding: {}", info.encoding);
+        println!("encrypted: {}", info.encrypted);
+        println!("key-id: {}", info.key_id);
+        let hashed = if info.kind == "shared" { "key" } else { "spki" };
+        println!("sha256-{}: {}", hashed, HEXLOWER.encode(&info.fingerprint));
+        if let Some(fingerprint) = &info.ssh_fingerprint {
+            println!(
+                "ssh-fingerprint: SHA256:{}",
+                STANDARD_NO_PAD.encode(fingerprint)
+            );
+        }
+        print!("{}", info.randomart);
+        Ok(())
+    }
+}
+
+impl CmdExector for KeyPubOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let key = crate::process_key_pub(
+            &self.key,
+            self.format,
+            self.role.public(),
+            &passphrase,
+            self.encoding,
+        )?;
+        match &self.output {
+            Some(path) => {
+                write_atomic(path, &key, self.force, 0o644)?;
+                eprintln!("Public key written to {}", path.display());
+            }
+            None => write_output(&key, None)?,
+        }
+        Ok(())
+    }
+}
+
+impl CmdExector for KeyConvertOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let key = crate::process_key_convert(
+            &self.key,
+            self.format,
+            self.role.public(),
+            &passphrase,
+            self.to,
+        )?;
+        self.output.write(&key)
+    }
+}
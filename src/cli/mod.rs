@@ -1,25 +1,47 @@
 mod base64;
+mod codec;
+mod compress;
+mod convert;
 mod csv;
+mod gen;
 mod genpass;
+mod hash;
 mod http;
 mod jwt;
+mod key;
+mod query;
 mod text;
 
+use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
 
 pub use self::base64::{Base64DecodeOpts, Base64EncodeOpts};
+pub use self::codec::{DecodeOpts, EncodeOpts, Encoding};
+pub use self::compress::{CompressAlgo, CompressOpts, DecompressOpts};
+pub use self::convert::ConvertOpts;
 pub use self::csv::CsvOpts;
-pub use self::genpass::GenPassOpts;
+pub use self::gen::{
+    GenNanoidOpts, GenSubCommand, GenTokenOpts, GenUlidOpts, GenUuidOpts, TokenEncoding,
+    UuidVersion,
+};
+pub use self::genpass::{GenPassCheckOpts, GenPassOpts, GenPassSubCommand, ReportFormat};
+pub use self::hash::{HashAlgo, HashOpts};
 pub use self::jwt::JwtSubCommand;
 pub use self::jwt::{ExpObj, JwtSignOpts, JwtVerifyOpts, TimeUnit};
-pub use self::text::{DecryptOpts, EncryptOpts, TextKeyGenerateOpts, TextSignOpts, TextVerifyOpts};
+pub use self::key::{KeyConvertOpts, KeyInfoOpts, KeyPubOpts, KeySubCommand};
+pub use self::query::{QueryOpts, SetOpts};
+pub use self::text::{
+    DecryptOpts, EncryptOpts, TextKeyGenerateOpts, TextSignDirOpts, TextSignOpts,
+    TextVerifyDirOpts, TextVerifyOpts,
+};
 
 pub use self::base64::Base64Format;
 pub use self::base64::Base64SubCommand;
 pub use self::csv::OutputFormat;
 pub use self::http::HttpServeOpts;
 pub use self::http::HttpSubCommand;
+pub use self::text::KeyEncoding;
 pub use self::text::TextCryptFormat;
 pub use self::text::TextSignFormat;
 pub use self::text::TextSubCommand;
@@ -40,15 +62,66 @@ pub enum SubCommand {
     #[command(name = "csv", about = "Show CSV, or convert CSV to other formats")]
     Csv(CsvOpts),
 
+    #[command(name = "convert", about = "Convert between JSON, YAML and TOML")]
+    Convert(ConvertOpts),
+
+    #[command(
+        name = "query",
+        about = "Query JSON, YAML or TOML with a jq-style filter"
+    )]
+    Query(QueryOpts),
+
+    #[command(name = "set", about = "Set values in a JSON, YAML or TOML document")]
+    Set(SetOpts),
+
     #[command(name = "genpass", about = "Generate a random password")]
     GenPass(GenPassOpts),
 
+    #[command(subcommand, about = "Generate random tokens and IDs")]
+    Gen(GenSubCommand),
+
     #[command(subcommand)]
     Base64(Base64SubCommand),
 
+    #[command(
+        name = "encode",
+        about = "Encode data as hex, base32, base58, base85, ..."
+    )]
+    Encode(EncodeOpts),
+
+    #[command(
+        name = "decode",
+        about = "Decode hex, base32, base58, base85, ... data"
+    )]
+    Decode(DecodeOpts),
+
+    #[command(
+        name = "hash",
+        about = "Hash files or stdin, or verify a checksum file"
+    )]
+    Hash(HashOpts),
+
+    #[command(
+        name = "compress",
+        about = "Compress with gzip, zstd, brotli, deflate or xz"
+    )]
+    Compress(CompressOpts),
+
+    #[command(
+        name = "decompress",
+        about = "Decompress gzip, zstd, brotli, deflate or xz"
+    )]
+    Decompress(DecompressOpts),
+
     #[command(subcommand)]
     Text(TextSubCommand),
 
+    #[command(
+        subcommand,
+        about = "Inspect key files, derive public keys and convert them"
+    )]
+    Key(KeySubCommand),
+
     #[command(subcommand)]
     Http(HttpSubCommand),
 
@@ -56,6 +129,75 @@ pub enum SubCommand {
     Jwt(JwtSubCommand),
 }
 
+/// Where generated secrets go, shared by every command that prints one
+#[derive(Debug, Parser)]
+pub struct SecretOutputOpts {
+    /// Write the secret to this file (mode 0600) instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite the output file if it already exists
+    #[arg(long, default_value_t = false, requires = "output")]
+    pub force: bool,
+
+    /// Print nothing but the secret itself to stdout
+    #[arg(long, default_value_t = false, conflicts_with = "output")]
+    pub stdout_only: bool,
+}
+
+impl SecretOutputOpts {
+    /// Whether informational messages may be printed to the terminal
+    pub fn verbose(&self) -> bool {
+        !self.stdout_only
+    }
+
+    pub fn write(&self, secret: &[u8]) -> anyhow::Result<()> {
+        match &self.output {
+            Some(path) => {
+                crate::utils::write_secret(path, secret, self.force)?;
+                eprintln!("Secret written to {}", path.display());
+            }
+            None => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(secret)?;
+                stdout.flush()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the passphrase protecting a private key comes from
+#[derive(Debug, Parser)]
+pub struct PassphraseOpts {
+    /// Prompt for the passphrase of the private key
+    #[arg(long, default_value_t = false, conflicts_with_all = ["passphrase_env", "passphrase_file"])]
+    pub passphrase: bool,
+
+    /// Read the passphrase from this environment variable
+    #[arg(long, value_name = "VAR", conflicts_with = "passphrase_file")]
+    pub passphrase_env: Option<String>,
+
+    /// Read the passphrase from the first line of this file
+    #[arg(long, value_name = "PATH", value_parser = verify_file)]
+    pub passphrase_file: Option<String>,
+}
+
+impl PassphraseOpts {
+    /// The passphrase source given on the command line, if any
+    pub fn source(&self) -> Option<crate::PassphraseSource> {
+        if let Some(name) = &self.passphrase_env {
+            Some(crate::PassphraseSource::Env(name.clone()))
+        } else if let Some(path) = &self.passphrase_file {
+            Some(crate::PassphraseSource::File(path.into()))
+        } else if self.passphrase {
+            Some(crate::PassphraseSource::Prompt)
+        } else {
+            None
+        }
+    }
+}
+
 fn verify_file(file_name: &str) -> Result<String, &'static str> {
     if file_name == "-" || Path::new(file_name).exists() {
         Ok(file_name.into())
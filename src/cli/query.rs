@@ -0,0 +1,88 @@
+use std::fs;
+
+use clap::Parser;
+
+use crate::CmdExector;
+
+use super::{verify_file, OutputFormat};
+
+#[derive(Debug, Parser)]
+pub struct QueryOpts {
+    /// jq-style filter, e.g. '.players[] | select(.Position=="GK") | .Name'
+    pub filter: String,
+
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Input format, taken from the file extension when omitted
+    #[arg(long, value_parser=parse_format)]
+    pub from: Option<OutputFormat>,
+
+    #[arg(long, value_parser=parse_format, default_value = "json")]
+    pub to: OutputFormat,
+
+    /// Print string results without quotes
+    #[arg(short, long, default_value_t = false)]
+    pub raw: bool,
+
+    /// Single-line JSON, inline TOML arrays
+    #[arg(long, default_value_t = false)]
+    pub compact: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct SetOpts {
+    /// `path=value` assignments such as server.port=9090 or hosts[0]=a,
+    /// values are parsed as JSON and fall back to plain strings
+    #[arg(required = true)]
+    pub assignments: Vec<String>,
+
+    #[arg(short,long,value_parser=verify_file,default_value="-")]
+    pub input: String,
+
+    /// Input format, taken from the file extension when omitted
+    #[arg(long, value_parser=parse_format)]
+    pub from: Option<OutputFormat>,
+
+    /// Output format, the input format when omitted
+    #[arg(long, value_parser=parse_format)]
+    pub to: Option<OutputFormat>,
+
+    /// Rewrite the input file instead of printing the result, comments are not kept
+    #[arg(long, default_value_t = false)]
+    pub in_place: bool,
+}
+
+impl CmdExector for QueryOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let output = crate::process_query(
+            &self.input,
+            self.from,
+            &self.filter,
+            self.to,
+            self.compact,
+            self.raw,
+        )?;
+        print!("{}", output);
+        Ok(())
+    }
+}
+
+impl CmdExector for SetOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        if self.in_place && self.input == "-" {
+            anyhow::bail!("--in-place needs an input file");
+        }
+        let output = crate::process_set(&self.input, self.from, self.to, &self.assignments)?;
+        if self.in_place {
+            fs::write(&self.input, output)?;
+        } else {
+            print!("{}", output);
+        }
+        Ok(())
+    }
+}
+
+fn parse_format(format: &str) -> Result<OutputFormat, anyhow::Error> {
+    format.parse()
+}
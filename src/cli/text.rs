@@ -1,12 +1,19 @@
-use std::{fmt, path::PathBuf, str::FromStr};
-
-use crate::CmdExector;
-
-use super::{verify_file, verify_path};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    utils::{write_atomic, write_secret},
+    CmdExector, ManifestStatus, PassphraseSource, SignatureFile,
+};
+
+use super::{verify_file, verify_path, PassphraseOpts};
 use anyhow::{Ok, Result};
 use clap::Parser;
 use enum_dispatch::enum_dispatch;
-use tokio::fs;
+use time::format_description::well_known::Rfc3339;
 
 #[derive(Debug, Parser)]
 #[enum_dispatch(CmdExector)]
@@ -16,6 +23,11 @@ pub enum TextSubCommand {
     #[command(about = "Verify a signed message")]
     Verify(TextVerifyOpts),
 
+    #[command(about = "Sign every file of a directory through a signed manifest")]
+    SignDir(TextSignDirOpts),
+    #[command(about = "Verify a directory against its signed manifest")]
+    VerifyDir(TextVerifyDirOpts),
+
     #[command(about = "Generate a new key")]
     Generate(TextKeyGenerateOpts),
 
@@ -66,6 +78,18 @@ pub struct TextSignOpts {
 
     #[arg(long, default_value="blake3", value_parser=parse_verify_format)]
     pub format: TextSignFormat,
+
+    /// Write a signature file with the algorithm, key id and timestamp instead of
+    /// printing the bare signature, a .minisig file for minisign
+    #[arg(long)]
+    pub out: Option<PathBuf>,
+
+    /// A single line comment stored in the signature file, the trusted comment for minisign
+    #[arg(long, requires = "out")]
+    pub comment: Option<String>,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
 }
 
 #[derive(Debug, Parser)]
@@ -79,8 +103,58 @@ pub struct TextVerifyOpts {
     #[arg(long, default_value="blake3", value_parser=parse_verify_format)]
     pub format: TextSignFormat,
 
-    #[arg(short, long)]
-    pub sig: String,
+    #[arg(short, long, required_unless_present = "sig_file")]
+    pub sig: Option<String>,
+
+    /// Verify against a signature file written by `text sign --out`,
+    /// which also gives the format
+    #[arg(long, value_parser=verify_file, conflicts_with_all = ["sig", "format"])]
+    pub sig_file: Option<String>,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct TextSignDirOpts {
+    #[arg(value_parser=verify_path)]
+    pub dir: PathBuf,
+
+    #[arg(short,long,value_parser=verify_file)]
+    pub key: String,
+
+    /// [default: detected from the key]
+    #[arg(long, value_parser=parse_verify_format)]
+    pub format: Option<TextSignFormat>,
+
+    /// Where to write the manifest, signed into <MANIFEST>.sig
+    /// [default: <DIR>/rcli-manifest.txt]
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    /// A single line comment stored in the signature file
+    #[arg(long)]
+    pub comment: Option<String>,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct TextVerifyDirOpts {
+    #[arg(value_parser=verify_path)]
+    pub dir: PathBuf,
+
+    #[arg(short,long,value_parser=verify_file)]
+    pub key: String,
+
+    /// The manifest written by `text sign-dir`, signed in <MANIFEST>.sig
+    /// [default: <DIR>/rcli-manifest.txt]
+    #[arg(long)]
+    pub manifest: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
 }
 
 #[derive(Debug, Parser)]
@@ -90,12 +164,47 @@ pub struct TextKeyGenerateOpts {
 
     #[arg(short,long,value_parser=verify_path)]
     pub output: PathBuf,
+
+    /// How the key is stored: raw, hex or base64 for blake3 and hmac-sha256 (default hex),
+    /// raw, pem or openssh for ed25519, minisign for minisign and raw or pem for the
+    /// others (default pem)
+    #[arg(long, value_parser=parse_key_encoding)]
+    pub encoding: Option<KeyEncoding>,
+
+    /// Overwrite existing key files
+    #[arg(long, default_value_t = false)]
+    pub force: bool,
+
+    /// Encrypt the private key with a passphrase
+    #[command(flatten)]
+    pub passphrase: PassphraseOpts,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TextSignFormat {
     Blake3,
     Ed25519,
+    /// ECDSA over NIST P-256
+    P256,
+    /// ECDSA over secp256k1
+    Secp256k1,
+    RsaPss,
+    HmacSha256,
+    /// Ed25519 keys and signatures in minisign's formats
+    Minisign,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum KeyEncoding {
+    Raw,
+    Hex,
+    Base64,
+    /// PKCS#8 private and SPKI public keys
+    Pem,
+    /// OpenSSH private key and `ssh-ed25519` public key
+    Openssh,
+    /// minisign secret and public key files
+    Minisign,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -107,10 +216,14 @@ fn parse_crypt_format(format: &str) -> Result<TextCryptFormat> {
     format.parse()
 }
 
-fn parse_verify_format(format: &str) -> Result<TextSignFormat> {
+pub(super) fn parse_verify_format(format: &str) -> Result<TextSignFormat> {
     format.parse()
 }
 
+pub(super) fn parse_key_encoding(encoding: &str) -> Result<KeyEncoding> {
+    encoding.parse()
+}
+
 impl FromStr for TextCryptFormat {
     type Err = anyhow::Error;
 
@@ -129,11 +242,32 @@ impl FromStr for TextSignFormat {
         match s {
             "blake3" => Ok(TextSignFormat::Blake3),
             "ed25519" => Ok(TextSignFormat::Ed25519),
+            "p256" => Ok(TextSignFormat::P256),
+            "secp256k1" => Ok(TextSignFormat::Secp256k1),
+            "rsa-pss" => Ok(TextSignFormat::RsaPss),
+            "hmac-sha256" => Ok(TextSignFormat::HmacSha256),
+            "minisign" => Ok(TextSignFormat::Minisign),
             _ => Err(anyhow::anyhow!("Invalid format")),
         }
     }
 }
 
+impl FromStr for KeyEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(KeyEncoding::Raw),
+            "hex" => Ok(KeyEncoding::Hex),
+            "base64" => Ok(KeyEncoding::Base64),
+            "pem" => Ok(KeyEncoding::Pem),
+            "openssh" => Ok(KeyEncoding::Openssh),
+            "minisign" => Ok(KeyEncoding::Minisign),
+            v => anyhow::bail!("Unsupported key encoding: {}", v),
+        }
+    }
+}
+
 impl From<TextCryptFormat> for &'static str {
     fn from(value: TextCryptFormat) -> Self {
         match value {
@@ -147,10 +281,34 @@ impl From<TextSignFormat> for &'static str {
         match value {
             TextSignFormat::Blake3 => "blake3",
             TextSignFormat::Ed25519 => "ed25519",
+            TextSignFormat::P256 => "p256",
+            TextSignFormat::Secp256k1 => "secp256k1",
+            TextSignFormat::RsaPss => "rsa-pss",
+            TextSignFormat::HmacSha256 => "hmac-sha256",
+            TextSignFormat::Minisign => "minisign",
+        }
+    }
+}
+
+impl From<KeyEncoding> for &'static str {
+    fn from(value: KeyEncoding) -> Self {
+        match value {
+            KeyEncoding::Raw => "raw",
+            KeyEncoding::Hex => "hex",
+            KeyEncoding::Base64 => "base64",
+            KeyEncoding::Pem => "pem",
+            KeyEncoding::Openssh => "openssh",
+            KeyEncoding::Minisign => "minisign",
         }
     }
 }
 
+impl fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
 impl fmt::Display for TextCryptFormat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", Into::<&str>::into(*self))
@@ -165,7 +323,20 @@ impl fmt::Display for TextSignFormat {
 
 impl CmdExector for TextSignOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let signed = crate::process_text_sign(&self.input, &self.key, self.format)?;
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        if let Some(out) = self.out {
+            let file = crate::process_text_sign_file(
+                &self.input,
+                &self.key,
+                self.format,
+                &passphrase,
+                self.comment,
+            )?;
+            write_atomic(&out, file.as_bytes(), true, 0o644)?;
+            eprintln!("Signature written to {}", out.display());
+            return Ok(());
+        }
+        let signed = crate::process_text_sign(&self.input, &self.key, self.format, &passphrase)?;
         println!("\nhash: {}", signed);
         Ok(())
     }
@@ -173,24 +344,123 @@ impl CmdExector for TextSignOpts {
 
 impl CmdExector for TextVerifyOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let verified = crate::process_text_verify(&self.input, &self.key, self.format, &self.sig)?;
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let verified = match (&self.sig_file, &self.sig) {
+            (Some(sig_file), _) => {
+                let file =
+                    crate::process_text_verify_file(&self.input, &self.key, sig_file, &passphrase)?;
+                if let Some(file) = &file {
+                    print_signature_file(file)?;
+                }
+                file.is_some()
+            }
+            (None, Some(sig)) => {
+                crate::process_text_verify(&self.input, &self.key, self.format, sig, &passphrase)?
+            }
+            (None, None) => unreachable!("clap requires --sig or --sig-file"),
+        };
         println!("\nverify: {}", verified);
+        if !verified {
+            anyhow::bail!("Signature verification failed");
+        }
+        Ok(())
+    }
+}
+
+impl CmdExector for TextSignDirOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let format = match self.format {
+            Some(format) => format,
+            None => crate::process_key_format(&self.key)?,
+        };
+        let (manifest_path, sig_path) = manifest_paths(&self.dir, self.manifest);
+        let manifest = crate::process_dir_manifest(&self.dir, &[&manifest_path, &sig_path])?;
+        write_atomic(&manifest_path, manifest.as_bytes(), true, 0o644)?;
+
+        let file = crate::process_text_sign_file(
+            &manifest_path.to_string_lossy(),
+            &self.key,
+            format,
+            &passphrase,
+            self.comment,
+        )?;
+        write_atomic(&sig_path, file.as_bytes(), true, 0o644)?;
+        eprintln!(
+            "Signed {} files into {} and {}",
+            manifest.lines().count() - 1,
+            manifest_path.display(),
+            sig_path.display()
+        );
+        Ok(())
+    }
+}
+
+impl CmdExector for TextVerifyDirOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let passphrase = self.passphrase.source().unwrap_or(PassphraseSource::Prompt);
+        let (manifest_path, sig_path) = manifest_paths(&self.dir, self.manifest);
+        let manifest = std::fs::read_to_string(&manifest_path)?;
+        let file = crate::process_text_verify_data(
+            manifest.as_bytes(),
+            &self.key,
+            &sig_path.to_string_lossy(),
+            &passphrase,
+        )?;
+        let Some(file) = file else {
+            anyhow::bail!("Manifest signature verification failed");
+        };
+        print_signature_file(&file)?;
+
+        let results =
+            crate::process_dir_manifest_check(&self.dir, &manifest, &[&manifest_path, &sig_path])?;
+        let (mut modified, mut missing, mut extra) = (0, 0, 0);
+        for (path, status) in &results {
+            match status {
+                ManifestStatus::Ok => continue,
+                ManifestStatus::Modified => modified += 1,
+                ManifestStatus::Missing => missing += 1,
+                ManifestStatus::Extra => extra += 1,
+            }
+            println!("{}: {}", path, status);
+        }
+        if modified + missing + extra > 0 {
+            anyhow::bail!(
+                "{} modified, {} missing and {} extra file(s)",
+                modified,
+                missing,
+                extra
+            );
+        }
+        println!("\nverified: {} files", results.len());
         Ok(())
     }
 }
 
 impl CmdExector for TextKeyGenerateOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let key = crate::process_generate_key(self.format)?;
-        match self.format {
-            TextSignFormat::Blake3 => {
-                let name = self.output.join("blake3.txt");
-                fs::write(name, &key[0]).await?;
+        let encoding = self.encoding.unwrap_or(match self.format {
+            TextSignFormat::Blake3 | TextSignFormat::HmacSha256 => KeyEncoding::Hex,
+            TextSignFormat::Minisign => KeyEncoding::Minisign,
+            _ => KeyEncoding::Pem,
+        });
+        let key =
+            crate::process_generate_key(self.format, encoding, self.passphrase.source().as_ref())?;
+        match key.as_slice() {
+            [shared] => {
+                let name = self.output.join(format!("{}.txt", self.format));
+                write_secret(name, shared, self.force)?;
             }
-            TextSignFormat::Ed25519 => {
-                fs::write(self.output.join("ed25519.sk"), &key[0]).await?;
-                fs::write(self.output.join("ed25519.pk"), &key[1]).await?;
+            [sk, pk] => {
+                write_secret(
+                    self.output.join(format!("{}.sk", self.format)),
+                    sk,
+                    self.force,
+                )?;
+                let name = self.output.join(format!("{}.pk", self.format));
+                write_atomic(name, pk, self.force, 0o644)?;
             }
+            _ => unreachable!("keys are generated alone or as a pair"),
         }
         Ok(())
     }
@@ -214,6 +484,26 @@ impl CmdExector for DecryptOpts {
     }
 }
 
+/// The manifest and its signature file, by default inside `dir`
+fn manifest_paths(dir: &Path, manifest: Option<PathBuf>) -> (PathBuf, PathBuf) {
+    let manifest = manifest.unwrap_or_else(|| dir.join("rcli-manifest.txt"));
+    let mut sig = manifest.clone().into_os_string();
+    sig.push(".sig");
+    (manifest, sig.into())
+}
+
+fn print_signature_file(file: &SignatureFile) -> anyhow::Result<()> {
+    println!("algorithm: {}", file.algorithm);
+    println!("key-id: {}", file.key_id);
+    if let Some(timestamp) = file.timestamp {
+        println!("timestamp: {}", timestamp.format(&Rfc3339)?);
+    }
+    if let Some(comment) = &file.comment {
+        println!("comment: {}", comment);
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -227,4 +517,46 @@ mod tests {
         assert_eq!("r:chacha20poly1305", r);
         Ok(())
     }
+
+    #[test]
+    fn test_sign_dir_verify_dir_round_trip() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path().to_string_lossy().into_owned();
+        std::fs::write(tmp.path().join("a.txt"), "hello")?;
+        let run = |args: &[&str]| {
+            let opts = crate::Opts::try_parse_from([&["rcli", "text"], args].concat())?;
+            tokio::runtime::Runtime::new()?.block_on(opts.cmd.execute())
+        };
+
+        run(&["sign-dir", &dir, "--key", "fixtures/ed25519.sk"])?;
+        let sig = std::fs::read_to_string(tmp.path().join("rcli-manifest.txt.sig"))?;
+        assert!(sig.contains("\nalgorithm: ed25519\n"));
+        run(&["verify-dir", &dir, "--key", "fixtures/ed25519.pk"])?;
+
+        std::fs::write(tmp.path().join("a.txt"), "HELLO")?;
+        assert!(run(&["verify-dir", &dir, "--key", "fixtures/ed25519.pk"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_dir_rejects_mac_made_with_public_key() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path().to_string_lossy().into_owned();
+        std::fs::write(tmp.path().join("a.txt"), "tampered")?;
+        let run = |args: &[&str]| {
+            let opts = crate::Opts::try_parse_from([&["rcli", "text"], args].concat())?;
+            tokio::runtime::Runtime::new()?.block_on(opts.cmd.execute())
+        };
+
+        // anyone holding the public key can compute this MAC
+        let key = "fixtures/ed25519.pk";
+        run(&["sign-dir", &dir, "--format", "hmac-sha256", "--key", key])?;
+        let err = run(&["verify-dir", &dir, "--key", key]).unwrap_err();
+        assert!(
+            err.to_string().contains("signature claims hmac-sha256"),
+            "{}",
+            err
+        );
+        Ok(())
+    }
 }
@@ -3,26 +3,54 @@ mod process;
 mod utils;
 
 pub use cli::{
-    Base64DecodeOpts, Base64EncodeOpts, CsvOpts, DecryptOpts, EncryptOpts, GenPassOpts,
-    JwtSignOpts, JwtVerifyOpts, TextKeyGenerateOpts, TextSignOpts, TextVerifyOpts,
+    Base64DecodeOpts, Base64EncodeOpts, CompressOpts, ConvertOpts, CsvOpts, DecodeOpts,
+    DecompressOpts, DecryptOpts, EncodeOpts, EncryptOpts, GenNanoidOpts, GenPassCheckOpts,
+    GenPassOpts, GenTokenOpts, GenUlidOpts, GenUuidOpts, HashOpts, JwtSignOpts, JwtVerifyOpts,
+    KeyConvertOpts, KeyInfoOpts, KeyPubOpts, QueryOpts, SetOpts, TextKeyGenerateOpts,
+    TextSignDirOpts, TextSignOpts, TextVerifyDirOpts, TextVerifyOpts,
 };
 pub use cli::{
-    Base64SubCommand, ExpObj, HttpServeOpts, HttpSubCommand, JwtSubCommand, Opts, SubCommand,
-    TextCryptFormat, TextSignFormat, TextSubCommand, TimeUnit,
+    Base64Format, Base64SubCommand, CompressAlgo, Encoding, ExpObj, GenPassSubCommand,
+    GenSubCommand, HashAlgo, HttpServeOpts, HttpSubCommand, JwtSubCommand, KeyEncoding,
+    KeySubCommand, Opts, PassphraseOpts, ReportFormat, SecretOutputOpts, SubCommand,
+    TextCryptFormat, TextSignFormat, TextSubCommand, TimeUnit, TokenEncoding, UuidVersion,
 };
 
 use enum_dispatch::enum_dispatch;
+pub use process::process_convert;
 pub use process::process_csv;
-pub use process::process_decode;
 pub use process::process_encode;
 pub use process::process_genpass;
+pub use process::process_genpass_check;
+pub use process::{mime_extension, process_decode_data_uri, process_encode_data_uri};
+pub use process::{process_auto_decode, process_codec_decode, process_codec_encode};
+pub use process::{
+    process_compress, process_decompress, process_decompress_reader, process_precompress_dir,
+    PrecompressStats,
+};
+pub use process::{process_decode, process_decode_reader};
 pub use process::{process_decrypt, process_encrypt};
+pub use process::{process_dir_manifest, process_dir_manifest_check, ManifestStatus};
+pub use process::{
+    process_gen_nanoid, process_gen_token, process_gen_ulid, process_gen_uuid, NANOID_ALPHABET,
+};
 pub use process::{process_generate_key, process_text_sign, process_text_verify};
+pub use process::{process_genpass_pattern, PRONOUNCEABLE_PATTERN};
+pub use process::{process_key_convert, process_key_info, process_key_pub, KeyInfo};
+pub use process::{
+    process_key_format, process_text_sign_file, process_text_verify_data, process_text_verify_file,
+    SignatureFile,
+};
+pub use process::{process_query, process_set};
 
 pub use process::process_http_serve;
 
+pub use process::{process_hash, process_hash_check, CheckStatus};
+
 pub use process::{process_jwt_sign, process_jwt_verify};
 
+pub use process::PassphraseSource;
+
 #[allow(async_fn_in_trait)]
 #[enum_dispatch]
 pub trait CmdExector {
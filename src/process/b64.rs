@@ -1,35 +1,376 @@
+use std::io::{self, Read, Write};
+
+use anyhow::Result;
 use base64::{
-    engine::general_purpose::{STANDARD, STANDARD_NO_PAD},
+    alphabet,
+    engine::{
+        general_purpose::{
+            GeneralPurpose, GeneralPurposeConfig, STANDARD, STANDARD_NO_PAD, URL_SAFE,
+            URL_SAFE_NO_PAD,
+        },
+        DecodePaddingMode,
+    },
+    read::DecoderReader,
+    write::EncoderWriter,
     Engine as _,
 };
 
 use crate::{cli::Base64Format, utils::get_reader};
 
-pub fn process_encode(input: &str, format: Base64Format) -> anyhow::Result<String> {
+/// MIME (RFC 2045) line length
+const MIME_LINE_LEN: usize = 76;
+
+/// Accepts padded and unpadded input, and stray bits at the end
+const LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new()
+        .with_decode_padding_mode(DecodePaddingMode::Indifferent)
+        .with_decode_allow_trailing_bits(true),
+);
+
+/// Stream `input` into `output` as base64, without holding the whole input in memory
+pub fn process_encode(input: &str, output: &mut dyn Write, format: Base64Format) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    encode_stream(&mut reader, output, format)
+}
+
+/// Stream base64 from `input` into `output`. Surrounding whitespace is skipped,
+/// and line breaks too in MIME or lenient mode, as [`decode_str`] does.
+pub fn process_decode(
+    input: &str,
+    output: &mut dyn Write,
+    format: Base64Format,
+    lenient: bool,
+) -> Result<()> {
     let mut reader = get_reader(input)?;
+    decode_stream(&mut reader, output, format, lenient)
+}
+
+/// Reader of the bytes decoded from `input`, for callers that may stop early
+pub fn process_decode_reader(
+    input: &str,
+    format: Base64Format,
+    lenient: bool,
+) -> Result<Box<dyn Read>> {
+    let reader = get_reader(input)?;
+    Ok(Box::new(decoder(reader, format, lenient)))
+}
+
+pub fn encode_stream(
+    reader: &mut dyn Read,
+    output: &mut dyn Write,
+    format: Base64Format,
+) -> Result<()> {
+    if let Base64Format::Mime = format {
+        let mut wrapped = LineWrapper::new(output, MIME_LINE_LEN);
+        let mut encoder = EncoderWriter::new(&mut wrapped, engine(format));
+        io::copy(reader, &mut encoder)?;
+        encoder.finish()?;
+    } else {
+        let mut encoder = EncoderWriter::new(output, engine(format));
+        io::copy(reader, &mut encoder)?;
+        encoder.finish()?;
+    }
+    Ok(())
+}
 
-    let mut buf = Vec::new();
-    reader.read_to_end(&mut buf)?;
+fn decode_stream(
+    reader: &mut dyn Read,
+    output: &mut dyn Write,
+    format: Base64Format,
+    lenient: bool,
+) -> Result<()> {
+    io::copy(&mut decoder(reader, format, lenient), output)?;
+    Ok(())
+}
 
-    let encode_data = match format {
-        Base64Format::Standard => STANDARD.encode(&buf),
-        Base64Format::UrlSafe => STANDARD_NO_PAD.encode(&buf),
+fn decoder<R: Read>(
+    reader: R,
+    format: Base64Format,
+    lenient: bool,
+) -> DecoderReader<'static, GeneralPurpose, Base64Filter<R>> {
+    let filtered = Base64Filter {
+        inner: reader,
+        lenient,
+        inner_whitespace: lenient || matches!(format, Base64Format::Mime),
+        started: false,
+        ended: false,
     };
-    Ok(encode_data)
+    let engine = if lenient { &LENIENT } else { engine(format) };
+    DecoderReader::new(filtered, engine)
 }
 
-pub fn process_decode(input: &str, format: Base64Format) -> anyhow::Result<Vec<u8>> {
-    let mut reader = get_reader(input)?;
+fn engine(format: Base64Format) -> &'static GeneralPurpose {
+    match format {
+        Base64Format::Standard | Base64Format::Mime => &STANDARD,
+        Base64Format::StandardNoPad => &STANDARD_NO_PAD,
+        Base64Format::UrlSafe => &URL_SAFE,
+        Base64Format::UrlSafeNoPad => &URL_SAFE_NO_PAD,
+    }
+}
+
+/// Drops whitespace from the base64 input, and in lenient mode
+/// maps the URL-safe alphabet onto the standard one
+struct Base64Filter<R> {
+    inner: R,
+    lenient: bool,
+    /// Whether whitespace may appear between the data, not only around it
+    inner_whitespace: bool,
+    started: bool,
+    /// Whitespace followed the data, so no more may come
+    ended: bool,
+}
 
-    let mut buf = String::new();
-    reader.read_to_string(&mut buf)?;
+impl<R: Read> Read for Base64Filter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut kept = 0;
+            for i in 0..n {
+                let c = match buf[i] {
+                    c if c.is_ascii_whitespace() => {
+                        self.ended = self.started && !self.inner_whitespace;
+                        continue;
+                    }
+                    _ if self.ended => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            "Whitespace inside base64 data, use --format mime or --lenient",
+                        ))
+                    }
+                    b'-' if self.lenient => b'+',
+                    b'_' if self.lenient => b'/',
+                    c => c,
+                };
+                buf[kept] = c;
+                kept += 1;
+                self.started = true;
+            }
+            if kept > 0 {
+                return Ok(kept);
+            }
+        }
+    }
+}
 
-    let buf = buf.trim();
+/// Breaks the output into CRLF separated lines of `width` bytes
+struct LineWrapper<'a> {
+    inner: &'a mut dyn Write,
+    width: usize,
+    col: usize,
+}
+
+impl<'a> LineWrapper<'a> {
+    fn new(inner: &'a mut dyn Write, width: usize) -> Self {
+        Self {
+            inner,
+            width,
+            col: 0,
+        }
+    }
+}
 
+impl Write for LineWrapper<'_> {
+    // always takes the whole buffer, EncoderWriter can't cope with short writes
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            // only break once more data follows, so there is no trailing CRLF
+            if self.col == self.width {
+                self.inner.write_all(b"\r\n")?;
+                self.col = 0;
+            }
+            let n = rest.len().min(self.width - self.col);
+            self.inner.write_all(&rest[..n])?;
+            self.col += n;
+            rest = &rest[n..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+pub fn encode_bytes(data: &[u8], format: Base64Format) -> String {
+    match format {
+        Base64Format::Standard => STANDARD.encode(data),
+        Base64Format::StandardNoPad => STANDARD_NO_PAD.encode(data),
+        Base64Format::UrlSafe => URL_SAFE.encode(data),
+        Base64Format::UrlSafeNoPad => URL_SAFE_NO_PAD.encode(data),
+        Base64Format::Mime => {
+            let encoded = STANDARD.encode(data);
+            encoded
+                .as_bytes()
+                .chunks(MIME_LINE_LEN)
+                .map(|line| std::str::from_utf8(line).expect("base64 is ascii"))
+                .collect::<Vec<_>>()
+                .join("\r\n")
+        }
+    }
+}
+
+/// Decode `data` in the given format. In lenient mode the format is ignored:
+/// both alphabets, missing padding and whitespace anywhere are accepted.
+pub fn decode_str(data: &str, format: Base64Format, lenient: bool) -> Result<Vec<u8>> {
+    if lenient {
+        let data: String = data
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                '-' => '+',
+                '_' => '/',
+                c => c,
+            })
+            .collect();
+        return Ok(LENIENT.decode(data)?);
+    }
+
+    let data = data.trim();
     let decode_data = match format {
-        Base64Format::Standard => STANDARD.decode(buf)?,
-        Base64Format::UrlSafe => STANDARD_NO_PAD.decode(buf)?,
+        Base64Format::Standard => STANDARD.decode(data)?,
+        Base64Format::StandardNoPad => STANDARD_NO_PAD.decode(data)?,
+        Base64Format::UrlSafe => URL_SAFE.decode(data)?,
+        Base64Format::UrlSafeNoPad => URL_SAFE_NO_PAD.decode(data)?,
+        Base64Format::Mime => {
+            let data: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+            STANDARD.decode(data)?
+        }
     };
-
     Ok(decode_data)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0xfb 0xff produce both `+` and `/` in the standard alphabet
+    const DATA: &[u8] = b"\xfb\xff\xbfrcli";
+
+    #[test]
+    fn test_encode_matrix() {
+        assert_eq!(encode_bytes(DATA, Base64Format::Standard), "+/+/cmNsaQ==");
+        assert_eq!(
+            encode_bytes(DATA, Base64Format::StandardNoPad),
+            "+/+/cmNsaQ"
+        );
+        assert_eq!(encode_bytes(DATA, Base64Format::UrlSafe), "-_-_cmNsaQ==");
+        assert_eq!(encode_bytes(DATA, Base64Format::UrlSafeNoPad), "-_-_cmNsaQ");
+        assert_eq!(encode_bytes(DATA, Base64Format::Mime), "+/+/cmNsaQ==");
+    }
+
+    #[test]
+    fn test_roundtrip_matrix() -> Result<()> {
+        let data: Vec<u8> = (0..=255).collect();
+        for format in [
+            Base64Format::Standard,
+            Base64Format::StandardNoPad,
+            Base64Format::UrlSafe,
+            Base64Format::UrlSafeNoPad,
+            Base64Format::Mime,
+        ] {
+            let encoded = encode_bytes(&data, format);
+            assert_eq!(decode_str(&encoded, format, false)?, data, "{}", format);
+            assert_eq!(decode_str(&encoded, format, true)?, data, "{}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_mime_wrapping() {
+        let encoded = encode_bytes(&[0u8; 100], Base64Format::Mime);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1].len(), 136 - 76);
+    }
+
+    #[test]
+    fn test_strict_decode_rejects_other_variants() {
+        assert!(decode_str("-_-_cmNsaQ==", Base64Format::Standard, false).is_err());
+        assert!(decode_str("+/+/cmNsaQ==", Base64Format::UrlSafe, false).is_err());
+        assert!(decode_str("-_-_cmNsaQ", Base64Format::UrlSafe, false).is_err());
+        assert!(decode_str("-_-_cmNsaQ==", Base64Format::UrlSafeNoPad, false).is_err());
+    }
+
+    #[test]
+    fn test_strict_whitespace_matches_str() -> Result<()> {
+        for (input, ok) in [
+            ("cmNsaQ==\n", true),
+            ("\n cmNsaQ==\r\n", true),
+            ("cmNs aQ==", false),
+            ("cmNs\naQ==", false),
+        ] {
+            let mut decoded = Vec::new();
+            let stream = decode_stream(
+                &mut input.as_bytes(),
+                &mut decoded,
+                Base64Format::Standard,
+                false,
+            )
+            .map(|_| decoded);
+            let bytes = decode_str(input, Base64Format::Standard, false);
+            assert_eq!(stream.is_ok(), ok, "{:?}", input);
+            assert_eq!(stream.ok(), bytes.ok(), "{:?}", input);
+        }
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut &b"cmNs\r\naQ=="[..],
+            &mut decoded,
+            Base64Format::Mime,
+            false,
+        )?;
+        assert_eq!(decoded, b"rcli");
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_matches_bytes() -> Result<()> {
+        // larger than the internal buffers of the encoder, decoder and io::copy
+        let data: Vec<u8> = (0..100_003u32).map(|i| (i * 7 % 251) as u8).collect();
+        for format in [
+            Base64Format::Standard,
+            Base64Format::StandardNoPad,
+            Base64Format::UrlSafe,
+            Base64Format::UrlSafeNoPad,
+            Base64Format::Mime,
+        ] {
+            let mut encoded = Vec::new();
+            encode_stream(&mut &data[..], &mut encoded, format)?;
+            assert_eq!(
+                String::from_utf8(encoded.clone())?,
+                encode_bytes(&data, format)
+            );
+
+            let mut decoded = Vec::new();
+            decode_stream(&mut &encoded[..], &mut decoded, format, false)?;
+            assert_eq!(decoded, data, "{}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient_stream_decode() -> Result<()> {
+        let mut decoded = Vec::new();
+        decode_stream(
+            &mut &b"-_+/ cmNs\r\naQ\n"[..],
+            &mut decoded,
+            Base64Format::Standard,
+            true,
+        )?;
+        assert_eq!(decoded, DATA);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lenient_decode() -> Result<()> {
+        for input in ["-_+/cmNsaQ", "+/-_ cmNs\r\naQ==", "-_-_\tcmNsaQ=\n"] {
+            assert_eq!(decode_str(input, Base64Format::Standard, true)?, DATA);
+        }
+        Ok(())
+    }
+}
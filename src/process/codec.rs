@@ -0,0 +1,262 @@
+use std::sync::OnceLock;
+
+use anyhow::Result;
+use data_encoding::{Specification, BASE32, HEXLOWER, HEXLOWER_PERMISSIVE, HEXUPPER};
+
+use crate::{cli::Encoding, utils::get_reader};
+
+use super::b64;
+
+const ASCII85: &[u8] =
+    b"!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstu";
+const Z85: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+pub fn process_codec_encode(input: &str, to: Encoding) -> Result<String> {
+    let mut reader = get_reader(input)?;
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf)?;
+    encode_bytes(&buf, to)
+}
+
+pub fn process_codec_decode(input: &str, from: Encoding) -> Result<Vec<u8>> {
+    let mut reader = get_reader(input)?;
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf)?;
+    decode_str(&buf, from)
+}
+
+pub fn encode_bytes(data: &[u8], to: Encoding) -> Result<String> {
+    let encoded = match to {
+        Encoding::Base64(format) => b64::encode_bytes(data, format),
+        Encoding::Hex => HEXLOWER.encode(data),
+        Encoding::HexUpper => HEXUPPER.encode(data),
+        Encoding::Base32 => BASE32.encode(data),
+        Encoding::Base32Crockford => crockford().encode(data),
+        Encoding::Base58 => bs58::encode(data).into_string(),
+        Encoding::Ascii85 => base85_encode(data, ASCII85, true),
+        Encoding::Z85 => {
+            if !data.len().is_multiple_of(4) {
+                anyhow::bail!("Z85 input length must be a multiple of 4");
+            }
+            base85_encode(data, Z85, false)
+        }
+        Encoding::Percent => percent_encode(data),
+    };
+    Ok(encoded)
+}
+
+pub fn decode_str(data: &str, from: Encoding) -> Result<Vec<u8>> {
+    let data = data.trim();
+    let decoded = match from {
+        Encoding::Base64(format) => b64::decode_str(data, format, false)?,
+        Encoding::Hex | Encoding::HexUpper => HEXLOWER_PERMISSIVE.decode(data.as_bytes())?,
+        Encoding::Base32 => BASE32.decode(data.as_bytes())?,
+        Encoding::Base32Crockford => crockford().decode(data.as_bytes())?,
+        Encoding::Base58 => bs58::decode(data).into_vec()?,
+        Encoding::Ascii85 => {
+            let data = data.strip_prefix("<~").unwrap_or(data);
+            let data = data.strip_suffix("~>").unwrap_or(data);
+            base85_decode(data, ASCII85, true)?
+        }
+        Encoding::Z85 => base85_decode(data, Z85, false)?,
+        Encoding::Percent => percent_decode(data)?,
+    };
+    Ok(decoded)
+}
+
+/// Crockford's base32: no padding, case insensitive, `I`/`L` read as 1, `O` as 0, `-` ignored
+fn crockford() -> &'static data_encoding::Encoding {
+    static CROCKFORD: OnceLock<data_encoding::Encoding> = OnceLock::new();
+    CROCKFORD.get_or_init(|| {
+        let mut spec = Specification::new();
+        spec.symbols.push_str("0123456789ABCDEFGHJKMNPQRSTVWXYZ");
+        spec.translate.from.push_str("abcdefghjkmnpqrstvwxyziloILO");
+        spec.translate.to.push_str("ABCDEFGHJKMNPQRSTVWXYZ110110");
+        spec.ignore.push('-');
+        spec.encoding().expect("valid crockford specification")
+    })
+}
+
+/// Base85 over 4 byte groups. Ascii85 pads the final group and shortens all-zero groups to `z`.
+fn base85_encode(data: &[u8], alphabet: &[u8], ascii85: bool) -> String {
+    let mut out = String::with_capacity(data.len() * 5 / 4 + 5);
+    for chunk in data.chunks(4) {
+        let mut group = [0u8; 4];
+        group[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(group);
+
+        if ascii85 && value == 0 && chunk.len() == 4 {
+            out.push('z');
+            continue;
+        }
+
+        let mut digits = [0u8; 5];
+        for d in digits.iter_mut().rev() {
+            *d = alphabet[(value % 85) as usize];
+            value /= 85;
+        }
+        out.extend(digits[..chunk.len() + 1].iter().map(|&b| b as char));
+    }
+    out
+}
+
+fn base85_decode(data: &str, alphabet: &[u8], ascii85: bool) -> Result<Vec<u8>> {
+    let mut lookup = [None; 256];
+    for (i, &c) in alphabet.iter().enumerate() {
+        lookup[c as usize] = Some(i as u32);
+    }
+
+    let mut out = Vec::with_capacity(data.len() * 4 / 5);
+    let mut group = Vec::with_capacity(5);
+    for c in data.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if ascii85 && c == b'z' && group.is_empty() {
+            out.extend_from_slice(&[0; 4]);
+            continue;
+        }
+        let digit = lookup[c as usize]
+            .ok_or_else(|| anyhow::anyhow!("Invalid base85 character: {:?}", c as char))?;
+        group.push(digit);
+        if group.len() == 5 {
+            out.extend_from_slice(&base85_group(&group)?);
+            group.clear();
+        }
+    }
+
+    if !group.is_empty() {
+        if !ascii85 || group.len() == 1 {
+            anyhow::bail!("Truncated base85 input");
+        }
+        let len = group.len();
+        group.resize(5, 84);
+        out.extend_from_slice(&base85_group(&group)?[..len - 1]);
+    }
+    Ok(out)
+}
+
+fn base85_group(digits: &[u32]) -> Result<[u8; 4]> {
+    let value = digits
+        .iter()
+        .try_fold(0u32, |acc, &d| acc.checked_mul(85)?.checked_add(d))
+        .ok_or_else(|| anyhow::anyhow!("Base85 group out of range"))?;
+    Ok(value.to_be_bytes())
+}
+
+/// RFC 3986 percent-encoding, everything but unreserved characters is escaped
+fn percent_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len() * 3);
+    for &b in data {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+fn percent_decode(data: &str) -> Result<Vec<u8>> {
+    let bytes = data.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .ok_or_else(|| anyhow::anyhow!("Truncated percent escape at {}", i))?;
+            out.extend(HEXLOWER_PERMISSIVE.decode(hex)?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Base64Format;
+
+    #[test]
+    fn test_known_vectors() -> Result<()> {
+        assert_eq!(
+            encode_bytes(b"\xde\xad\xbe\xef", Encoding::Hex)?,
+            "deadbeef"
+        );
+        assert_eq!(
+            encode_bytes(b"\xde\xad\xbe\xef", Encoding::HexUpper)?,
+            "DEADBEEF"
+        );
+        assert_eq!(
+            encode_bytes(b"foobar", Encoding::Base32)?,
+            "MZXW6YTBOI======"
+        );
+        assert_eq!(
+            encode_bytes(b"Hello World!", Encoding::Base58)?,
+            "2NEpo7TZRRrLZSi2U"
+        );
+        assert_eq!(encode_bytes(b"Man is d", Encoding::Ascii85)?, "9jqo^BlbD-");
+        assert_eq!(encode_bytes(&[0; 4], Encoding::Ascii85)?, "z");
+        assert_eq!(
+            encode_bytes(b"\x86\x4F\xD2\x6F\xB5\x59\xF7\x5B", Encoding::Z85)?,
+            "HelloWorld"
+        );
+        assert_eq!(
+            encode_bytes(b"a b/c?=\xff", Encoding::Percent)?,
+            "a%20b%2Fc%3F%3D%FF"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        let data: Vec<u8> = (0..=255).rev().collect();
+        for encoding in [
+            Encoding::Base64(Base64Format::UrlSafe),
+            Encoding::Hex,
+            Encoding::HexUpper,
+            Encoding::Base32,
+            Encoding::Base32Crockford,
+            Encoding::Base58,
+            Encoding::Ascii85,
+            Encoding::Z85,
+            Encoding::Percent,
+        ] {
+            for len in [0usize, 1, 2, 3, 4, 5, 255, 256] {
+                if matches!(encoding, Encoding::Z85) && !len.is_multiple_of(4) {
+                    continue;
+                }
+                let encoded = encode_bytes(&data[..len], encoding)?;
+                assert_eq!(
+                    decode_str(&encoded, encoding)?,
+                    &data[..len],
+                    "{}",
+                    encoding
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lax_decoding() -> Result<()> {
+        assert_eq!(decode_str("DeadBeef", Encoding::Hex)?, b"\xde\xad\xbe\xef");
+        let crockford = encode_bytes(b"rcli", Encoding::Base32Crockford)?;
+        assert_eq!(
+            decode_str(
+                &crockford.to_lowercase().replace('1', "l"),
+                Encoding::Base32Crockford
+            )?,
+            b"rcli"
+        );
+        assert_eq!(
+            decode_str("<~9jqo^BlbD-~>", Encoding::Ascii85)?,
+            b"Man is d"
+        );
+        assert!(encode_bytes(b"abc", Encoding::Z85).is_err());
+        assert!(decode_str("%4", Encoding::Percent).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use std::{
+    fs,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use flate2::{
+    read::{MultiGzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+use xz2::{read::XzDecoder, write::XzEncoder};
+
+use super::hash::collect_files;
+use crate::{cli::CompressAlgo, utils::get_reader, utils::write_atomic};
+
+const BROTLI_BUFFER_SIZE: usize = 64 * 1024;
+const BROTLI_WINDOW_BITS: u32 = 22;
+
+/// Bytes needed to recognize every signature in `detect_algo`
+const SNIFF_LEN: usize = 6;
+
+/// The encodings `ServeDir` negotiates and the sibling extension it looks for
+const PRECOMPRESS_VARIANTS: &[(CompressAlgo, &str)] = &[
+    (CompressAlgo::Gzip, "gz"),
+    (CompressAlgo::Brotli, "br"),
+    (CompressAlgo::Zstd, "zst"),
+    (CompressAlgo::Deflate, "zz"),
+];
+
+/// Formats that are already compressed, precompressing them only wastes disk
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "gz", "br", "zst", "zz", "xz", "zip", "png", "jpg", "jpeg", "gif", "webp", "avif", "woff",
+    "woff2", "mp3", "mp4", "webm", "ogg",
+];
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PrecompressStats {
+    pub files: usize,
+    pub written: usize,
+    pub skipped: usize,
+}
+
+pub fn process_compress(
+    input: &str,
+    output: &mut dyn Write,
+    algo: CompressAlgo,
+    level: Option<u32>,
+) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    compress_stream(&mut reader, output, algo, level)
+}
+
+/// Decompress `input`, detecting the algorithm from its leading bytes when `algo` is `None`
+pub fn process_decompress(
+    input: &str,
+    output: &mut dyn Write,
+    algo: Option<CompressAlgo>,
+) -> Result<()> {
+    let (mut reader, algo) = open_compressed(input, algo)?;
+    decompress_stream(&mut reader, output, algo)
+}
+
+/// Reader of the decompressed bytes of `input`, for callers that may stop early
+pub fn process_decompress_reader(input: &str, algo: Option<CompressAlgo>) -> Result<Box<dyn Read>> {
+    let (reader, algo) = open_compressed(input, algo)?;
+    decoder(reader, algo)
+}
+
+/// Open `input` and pick the algorithm, sniffing its leading bytes when `algo` is `None`
+fn open_compressed(input: &str, algo: Option<CompressAlgo>) -> Result<(impl Read, CompressAlgo)> {
+    let mut reader = get_reader(input)?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    reader
+        .by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+
+    let algo = match algo {
+        Some(algo) => algo,
+        None => detect_algo(&head).ok_or_else(|| {
+            anyhow::anyhow!("Unable to detect the compression format, use --algo")
+        })?,
+    };
+    Ok((Cursor::new(head).chain(reader), algo))
+}
+
+/// Write a compressed sibling of every file under `dir` for each encoding `ServeDir` serves.
+/// Without `level` each algorithm uses its strongest setting, a given level is clamped
+/// to each algorithm's range. Variants that would not be smaller are not written.
+pub fn process_precompress_dir(dir: &Path, level: Option<u32>) -> Result<PrecompressStats> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    let files = files
+        .into_iter()
+        .map(PathBuf::from)
+        .filter(|f| !is_compressed(f));
+
+    let mut stats = PrecompressStats::default();
+    for file in files {
+        stats.files += 1;
+        let data = fs::read(&file)?;
+        for &(algo, ext) in PRECOMPRESS_VARIANTS {
+            let (min, max) = level_range(algo);
+            let level = level.map_or(max, |level| level.clamp(min, max));
+            let mut compressed = Vec::new();
+            compress_stream(&mut data.as_slice(), &mut compressed, algo, Some(level))?;
+            if compressed.len() >= data.len() {
+                stats.skipped += 1;
+                continue;
+            }
+            write_atomic(variant_path(&file, ext), &compressed, true, 0o644)?;
+            stats.written += 1;
+        }
+    }
+    Ok(stats)
+}
+
+pub fn compress_stream(
+    reader: &mut dyn Read,
+    output: &mut dyn Write,
+    algo: CompressAlgo,
+    level: Option<u32>,
+) -> Result<()> {
+    let level = match level {
+        Some(level) => {
+            let (min, max) = level_range(algo);
+            if !(min..=max).contains(&level) {
+                anyhow::bail!("{} level must be between {} and {}", algo, min, max);
+            }
+            level
+        }
+        None => default_level(algo),
+    };
+
+    match algo {
+        CompressAlgo::Gzip => {
+            let mut encoder = GzEncoder::new(output, Compression::new(level));
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressAlgo::Deflate => {
+            let mut encoder = ZlibEncoder::new(output, Compression::new(level));
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressAlgo::Zstd => {
+            let mut encoder = zstd::Encoder::new(output, level as i32)?;
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+        CompressAlgo::Brotli => {
+            let mut encoder = brotli::CompressorWriter::new(
+                output,
+                BROTLI_BUFFER_SIZE,
+                level,
+                BROTLI_WINDOW_BITS,
+            );
+            io::copy(reader, &mut encoder)?;
+            encoder.flush()?;
+            // into_inner writes the final block
+            encoder.into_inner().flush()?;
+        }
+        CompressAlgo::Xz => {
+            let mut encoder = XzEncoder::new(output, level);
+            io::copy(reader, &mut encoder)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+pub fn decompress_stream(
+    reader: &mut dyn Read,
+    output: &mut dyn Write,
+    algo: CompressAlgo,
+) -> Result<()> {
+    io::copy(&mut decoder(reader, algo)?, output)?;
+    Ok(())
+}
+
+fn decoder<'a, R: Read + 'a>(reader: R, algo: CompressAlgo) -> Result<Box<dyn Read + 'a>> {
+    let decoder: Box<dyn Read + 'a> = match algo {
+        CompressAlgo::Gzip => Box::new(MultiGzDecoder::new(reader)),
+        CompressAlgo::Deflate => Box::new(ZlibDecoder::new(reader)),
+        CompressAlgo::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        CompressAlgo::Brotli => Box::new(brotli::Decompressor::new(reader, BROTLI_BUFFER_SIZE)),
+        CompressAlgo::Xz => Box::new(XzDecoder::new_multi_decoder(reader)),
+    };
+    Ok(decoder)
+}
+
+/// Brotli has no magic number, so it can only be selected explicitly
+fn detect_algo(head: &[u8]) -> Option<CompressAlgo> {
+    match head {
+        [0x1f, 0x8b, ..] => Some(CompressAlgo::Gzip),
+        [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(CompressAlgo::Zstd),
+        [0xfd, b'7', b'z', b'X', b'Z', 0x00, ..] => Some(CompressAlgo::Xz),
+        [0x78, b, ..] if u16::from_be_bytes([0x78, *b]).is_multiple_of(31) => {
+            Some(CompressAlgo::Deflate)
+        }
+        _ => None,
+    }
+}
+
+fn level_range(algo: CompressAlgo) -> (u32, u32) {
+    match algo {
+        CompressAlgo::Gzip | CompressAlgo::Deflate | CompressAlgo::Xz => (0, 9),
+        CompressAlgo::Zstd => (1, 22),
+        CompressAlgo::Brotli => (0, 11),
+    }
+}
+
+fn default_level(algo: CompressAlgo) -> u32 {
+    match algo {
+        CompressAlgo::Gzip | CompressAlgo::Deflate | CompressAlgo::Xz => 6,
+        CompressAlgo::Zstd => 3,
+        // brotli's own default of 11 is too slow for ad hoc use
+        CompressAlgo::Brotli => 6,
+    }
+}
+
+/// `index.html` -> `index.html.gz`
+fn variant_path(file: &Path, ext: &str) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".");
+    path.push(ext);
+    path.into()
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| COMPRESSED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGOS: [CompressAlgo; 5] = [
+        CompressAlgo::Gzip,
+        CompressAlgo::Zstd,
+        CompressAlgo::Brotli,
+        CompressAlgo::Deflate,
+        CompressAlgo::Xz,
+    ];
+
+    #[test]
+    fn test_compress_round_trip() -> Result<()> {
+        let data = "rcli compresses things. ".repeat(200);
+        for algo in ALGOS {
+            let mut compressed = Vec::new();
+            compress_stream(&mut data.as_bytes(), &mut compressed, algo, None)?;
+            assert!(compressed.len() < data.len(), "{}", algo);
+            if algo != CompressAlgo::Brotli {
+                assert_eq!(detect_algo(&compressed), Some(algo));
+            }
+
+            let mut decompressed = Vec::new();
+            decompress_stream(&mut compressed.as_slice(), &mut decompressed, algo)?;
+            assert_eq!(decompressed, data.as_bytes(), "{}", algo);
+        }
+        assert!(
+            compress_stream(&mut &b""[..], &mut Vec::new(), CompressAlgo::Zstd, Some(23)).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_precompress_dir() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("css"))?;
+        fs::write(dir.join("index.html"), "<p>hello</p>".repeat(100))?;
+        fs::write(dir.join("css/site.css"), "body { margin: 0 }\n".repeat(100))?;
+        fs::write(dir.join("logo.png"), [0x89, b'P', b'N', b'G'])?;
+        fs::write(dir.join("tiny.txt"), "a")?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink("..", dir.join("css/loop"))?;
+
+        let stats = process_precompress_dir(dir, None)?;
+        assert_eq!(
+            stats,
+            PrecompressStats {
+                files: 3,
+                written: 8,
+                skipped: 4
+            }
+        );
+        let gz = fs::read(dir.join("css/site.css.gz"))?;
+        let mut css = Vec::new();
+        decompress_stream(&mut gz.as_slice(), &mut css, CompressAlgo::Gzip)?;
+        assert_eq!(css, fs::read(dir.join("css/site.css"))?);
+        assert!(dir.join("index.html.zz").exists());
+        assert!(!dir.join("tiny.txt.br").exists());
+
+        // existing variants are skipped rather than compressed again
+        assert_eq!(process_precompress_dir(dir, Some(1))?.files, 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use std::{io::Read, path::Path};
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::{cli::OutputFormat, utils::get_reader};
+
+/// Convert a JSON, YAML or TOML document to another of those formats.
+/// `from` is taken from the file extension when not given.
+pub fn process_convert(
+    input: &str,
+    from: Option<OutputFormat>,
+    to: OutputFormat,
+    compact: bool,
+    sort_keys: bool,
+) -> Result<String> {
+    let from = match from {
+        Some(from) => from,
+        None => format_from_path(input)?,
+    };
+    let mut reader = get_reader(input)?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+
+    let name = if input == "-" { "<stdin>" } else { input };
+    let mut value =
+        parse(&content, from).map_err(|e| anyhow::anyhow!("{}: invalid {}: {}", name, from, e))?;
+    if sort_keys {
+        value = sort_value(value);
+    }
+    serialize(&value, to, compact)
+}
+
+pub fn parse(content: &str, format: OutputFormat) -> Result<Value> {
+    let value = match format {
+        OutputFormat::Json => serde_json::from_str(content)?,
+        OutputFormat::Yaml => serde_yaml::from_str(content)?,
+        OutputFormat::Toml => toml_to_json(toml::from_str(content)?),
+    };
+    Ok(value)
+}
+
+/// `compact` only changes JSON and TOML, YAML has a single block style
+pub fn serialize(value: &Value, format: OutputFormat, compact: bool) -> Result<String> {
+    let mut content = match format {
+        OutputFormat::Json if compact => serde_json::to_string(value)?,
+        OutputFormat::Json => serde_json::to_string_pretty(value)?,
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        OutputFormat::Toml => {
+            if !value.is_object() {
+                anyhow::bail!("TOML documents must be a table at the top level");
+            }
+            if has_null(value) {
+                anyhow::bail!("TOML has no null, remove or replace the null values first");
+            }
+            if compact {
+                toml::to_string(value)?
+            } else {
+                toml::to_string_pretty(value)?
+            }
+        }
+    };
+    if !content.ends_with('\n') {
+        content.push('\n');
+    }
+    Ok(content)
+}
+
+pub fn format_from_path(input: &str) -> Result<OutputFormat> {
+    let ext = Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("json") => Ok(OutputFormat::Json),
+        Some("yaml" | "yml") => Ok(OutputFormat::Yaml),
+        Some("toml") => Ok(OutputFormat::Toml),
+        _ => anyhow::bail!("Unable to tell the format of {}, use --from", input),
+    }
+}
+
+/// TOML datetimes would otherwise serialize as a private wrapper table
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+fn has_null(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(items) => items.iter().any(has_null),
+        Value::Object(map) => map.values().any(has_null),
+        _ => false,
+    }
+}
+
+fn sort_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_value(v)))
+                    .collect::<Map<_, _>>(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_value).collect()),
+        v => v,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = "name: rcli\nversion: 1\nserver:\n  port: 8080\n  hosts: [a, b]\n";
+
+    #[test]
+    fn test_convert_round_trip() -> Result<()> {
+        let value = parse(YAML, OutputFormat::Yaml)?;
+        for format in [OutputFormat::Json, OutputFormat::Yaml, OutputFormat::Toml] {
+            for compact in [false, true] {
+                let content = serialize(&value, format, compact)?;
+                assert_eq!(parse(&content, format)?, value, "{}", content);
+            }
+        }
+
+        let toml = serialize(&value, OutputFormat::Toml, true)?;
+        assert!(
+            toml.starts_with("name = \"rcli\"\nversion = 1\n"),
+            "{}",
+            toml
+        );
+        assert!(toml.contains("[server]"));
+        assert_eq!(
+            serialize(&sort_value(value), OutputFormat::Json, true)?,
+            "{\"name\":\"rcli\",\"server\":{\"hosts\":[\"a\",\"b\"],\"port\":8080},\"version\":1}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_toml_datetime_and_errors() -> Result<()> {
+        let value = parse("built = 1979-05-27T07:32:00Z", OutputFormat::Toml)?;
+        assert_eq!(value["built"], "1979-05-27T07:32:00Z");
+
+        let err = parse("{\n  \"a\": 1,\n  \"b\" 2\n}", OutputFormat::Json).unwrap_err();
+        assert!(err.to_string().contains("line 3 column 7"), "{}", err);
+        let err = parse("a = 1\nb = \n", OutputFormat::Toml).unwrap_err();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+        assert!(serialize(&Value::from(1), OutputFormat::Toml, false).is_err());
+        let null = parse("{\"a\": [null]}", OutputFormat::Json)?;
+        assert!(serialize(&null, OutputFormat::Toml, false).is_err());
+        Ok(())
+    }
+}
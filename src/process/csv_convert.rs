@@ -58,3 +58,32 @@ pub fn process_csv(input: &str, output: String, format: OutputFormat) -> anyhow:
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_keeps_header_order() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let output = tmp.path().join("juventus.json");
+        process_csv(
+            "assets/juventus.csv",
+            output.to_string_lossy().into_owned(),
+            OutputFormat::Json,
+        )?;
+
+        let players: Vec<Value> = serde_json::from_str(&fs::read_to_string(&output)?)?;
+        let keys: Vec<&str> = players[0]
+            .as_object()
+            .expect("an object per row")
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            keys,
+            ["Name", "Position", "DOB", "Nationality", "Kit Number"]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+
+use anyhow::Result;
+
+use crate::{
+    cli::{Base64Format, Encoding},
+    utils::get_reader,
+};
+
+use super::{b64, codec};
+
+const DEFAULT_MIME: &str = "application/octet-stream";
+
+/// Bytes needed to recognize every signature in `sniff_magic`
+const SNIFF_LEN: usize = 16;
+
+/// Known media types and their preferred file extension, first match wins for lookups
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/jpeg", "jpeg"),
+    ("image/gif", "gif"),
+    ("image/webp", "webp"),
+    ("image/svg+xml", "svg"),
+    ("image/x-icon", "ico"),
+    ("image/bmp", "bmp"),
+    ("image/avif", "avif"),
+    ("font/woff", "woff"),
+    ("font/woff2", "woff2"),
+    ("font/ttf", "ttf"),
+    ("font/otf", "otf"),
+    ("audio/mpeg", "mp3"),
+    ("audio/ogg", "ogg"),
+    ("audio/wav", "wav"),
+    ("video/mp4", "mp4"),
+    ("video/webm", "webm"),
+    ("application/pdf", "pdf"),
+    ("application/zip", "zip"),
+    ("application/gzip", "gz"),
+    ("application/wasm", "wasm"),
+    ("application/json", "json"),
+    ("text/css", "css"),
+    ("text/javascript", "js"),
+    ("text/html", "html"),
+    ("text/csv", "csv"),
+    ("text/plain", "txt"),
+    (DEFAULT_MIME, "bin"),
+];
+
+/// Encode `input` as a `data:<mime>;base64,` URI. The media type is sniffed
+/// from the leading bytes, falling back to the file extension.
+pub fn process_encode_data_uri(input: &str, output: &mut dyn Write) -> Result<()> {
+    let mut reader = get_reader(input)?;
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    reader
+        .by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut head)?;
+
+    let path = (input != "-").then(|| Path::new(input));
+    write!(output, "data:{};base64,", sniff_mime(&head, path))?;
+
+    let mut reader = Cursor::new(head).chain(reader);
+    b64::encode_stream(&mut reader, output, Base64Format::Standard)
+}
+
+/// Parse a data URI, returning its media type and payload
+pub fn process_decode_data_uri(input: &str) -> Result<(String, Vec<u8>)> {
+    let mut reader = get_reader(input)?;
+    let mut buf = String::new();
+    reader.read_to_string(&mut buf)?;
+    parse_data_uri(buf.trim())
+}
+
+pub fn parse_data_uri(uri: &str) -> Result<(String, Vec<u8>)> {
+    let rest = uri
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .map(|_| &uri[5..])
+        .ok_or_else(|| anyhow::anyhow!("Not a data URI"))?;
+    let (meta, payload) = rest
+        .split_once(',')
+        .ok_or_else(|| anyhow::anyhow!("Data URI has no ',' before the payload"))?;
+
+    let (meta, is_base64) = match meta.strip_suffix(";base64") {
+        Some(meta) => (meta, true),
+        None => (meta, false),
+    };
+    // RFC 2397: a missing media type means text/plain
+    let mime = match meta.split(';').next() {
+        Some(mime) if !mime.is_empty() => mime.to_ascii_lowercase(),
+        _ => "text/plain".to_string(),
+    };
+
+    let data = if is_base64 {
+        b64::decode_str(payload, Base64Format::Standard, true)?
+    } else {
+        codec::decode_str(payload, Encoding::Percent)?
+    };
+    Ok((mime, data))
+}
+
+pub fn sniff_mime(head: &[u8], path: Option<&Path>) -> &'static str {
+    sniff_magic(head)
+        .or_else(|| {
+            let ext = path?.extension()?.to_str()?.to_ascii_lowercase();
+            MIME_TYPES
+                .iter()
+                .find(|(_, e)| *e == ext)
+                .map(|(mime, _)| *mime)
+        })
+        .unwrap_or(DEFAULT_MIME)
+}
+
+pub fn mime_extension(mime: &str) -> &'static str {
+    MIME_TYPES
+        .iter()
+        .find(|(m, _)| m.eq_ignore_ascii_case(mime))
+        .map(|(_, ext)| *ext)
+        .unwrap_or("bin")
+}
+
+pub(super) fn sniff_magic(head: &[u8]) -> Option<&'static str> {
+    let riff = |kind: &[u8]| head.starts_with(b"RIFF") && head.get(8..12) == Some(kind);
+    let mime = match head {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        _ if riff(b"WEBP") => "image/webp",
+        _ if riff(b"WAVE") => "audio/wav",
+        _ if is_ico(head) => "image/x-icon",
+        _ if is_bmp(head) => "image/bmp",
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f', ..] => "image/avif",
+        [_, _, _, _, b'f', b't', b'y', b'p', ..] => "video/mp4",
+        [0x1a, 0x45, 0xdf, 0xa3, ..] => "video/webm",
+        [b'w', b'O', b'F', b'F', ..] => "font/woff",
+        [b'w', b'O', b'F', b'2', ..] => "font/woff2",
+        _ if is_ttf(head) => "font/ttf",
+        [b'O', b'T', b'T', b'O', ..] => "font/otf",
+        [b'I', b'D', b'3', ..] | [0xff, 0xfb, ..] => "audio/mpeg",
+        [b'O', b'g', b'g', b'S', ..] => "audio/ogg",
+        [b'%', b'P', b'D', b'F', ..] => "application/pdf",
+        [b'P', b'K', 3, 4, ..] => "application/zip",
+        [0x1f, 0x8b, ..] => "application/gzip",
+        [0, b'a', b's', b'm', ..] => "application/wasm",
+        _ if head.starts_with(b"<svg") => "image/svg+xml",
+        _ => return None,
+    };
+    Some(mime)
+}
+
+/// "BM", the reserved bytes zeroed and a known DIB header size
+fn is_bmp(head: &[u8]) -> bool {
+    let dib_len = |h: &[u8]| u32::from_le_bytes([h[14], h[15], h[16], h[17]]);
+    head.len() >= 18
+        && head.starts_with(b"BM")
+        && head[6..10] == [0; 4]
+        && matches!(dib_len(head), 12 | 40 | 52 | 56 | 108 | 124)
+}
+
+/// An icon directory with at least one image, whose first entry has its reserved byte
+/// zeroed and 0 or 1 color planes
+fn is_ico(head: &[u8]) -> bool {
+    head.len() >= 22
+        && head.starts_with(&[0, 0, 1, 0])
+        && u16::from_le_bytes([head[4], head[5]]) > 0
+        && head[9] == 0
+        && matches!(u16::from_le_bytes([head[10], head[11]]), 0 | 1)
+}
+
+/// The TrueType version, and a search range that agrees with the number of tables
+fn is_ttf(head: &[u8]) -> bool {
+    if head.len() < 8 || !head.starts_with(&[0, 1, 0, 0]) {
+        return false;
+    }
+    let tables = u16::from_be_bytes([head[4], head[5]]);
+    let search_range = u16::from_be_bytes([head[6], head[7]]);
+    tables > 0 && u32::from(search_range) == 16 << tables.ilog2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_mime() {
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        assert_eq!(sniff_mime(png, Some(Path::new("logo.bin"))), "image/png");
+        assert_eq!(sniff_mime(b"body{}", Some(Path::new("a.CSS"))), "text/css");
+        assert_eq!(sniff_mime(b"\x00\x01\x02", None), DEFAULT_MIME);
+
+        let bmp = b"BM\x46\0\0\0\0\0\0\0\x36\0\0\0\x28\0\0\0";
+        assert_eq!(sniff_mime(bmp, None), "image/bmp");
+        assert_eq!(
+            sniff_mime(b"BMW 320i, 1991, low mileage", None),
+            DEFAULT_MIME
+        );
+        let ico = b"\0\0\x01\0\x01\0\x10\x10\0\0\x01\0\x20\0\x68\x04\0\0\x16\0\0\0";
+        assert_eq!(sniff_mime(ico, None), "image/x-icon");
+        assert_eq!(
+            sniff_mime(b"\0\0\x01\0\0\0garbage garbage", None),
+            DEFAULT_MIME
+        );
+        let ttf = b"\0\x01\0\0\0\x0a\0\x80\0\x03\0\x20";
+        assert_eq!(sniff_mime(ttf, None), "font/ttf");
+        assert_eq!(
+            sniff_mime(b"\0\x01\0\0\xff\xff\xff\xff", None),
+            DEFAULT_MIME
+        );
+        assert_eq!(mime_extension("image/JPEG"), "jpg");
+        assert_eq!(mime_extension("application/x-unknown"), "bin");
+    }
+
+    #[test]
+    fn test_parse_data_uri() -> Result<()> {
+        let (mime, data) = parse_data_uri("data:image/png;base64,iVBORw0KGgo=")?;
+        assert_eq!(mime, "image/png");
+        assert_eq!(data, b"\x89PNG\r\n\x1a\n");
+
+        let (mime, data) = parse_data_uri("data:,Hello%2C%20World%21")?;
+        assert_eq!(mime, "text/plain");
+        assert_eq!(data, b"Hello, World!");
+
+        let (mime, _) = parse_data_uri("DATA:text/html;charset=utf-8,<p>")?;
+        assert_eq!(mime, "text/html");
+
+        assert!(parse_data_uri("http://example.com").is_err());
+        Ok(())
+    }
+}
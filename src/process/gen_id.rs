@@ -0,0 +1,130 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use data_encoding::{BASE32_NOPAD, HEXLOWER};
+use rand::{rngs::OsRng, seq::SliceRandom, RngCore};
+
+use crate::cli::{TokenEncoding, UuidVersion};
+
+const CROCKFORD: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+pub const NANOID_ALPHABET: &str =
+    "_-0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+pub fn process_gen_token(bytes: usize, encoding: TokenEncoding) -> Result<String> {
+    if bytes == 0 {
+        anyhow::bail!("Token must be at least 1 byte");
+    }
+    let mut buf = vec![0u8; bytes];
+    OsRng.fill_bytes(&mut buf);
+
+    let token = match encoding {
+        TokenEncoding::Hex => HEXLOWER.encode(&buf),
+        TokenEncoding::Base64Url => URL_SAFE_NO_PAD.encode(&buf),
+        TokenEncoding::Base58 => bs58::encode(&buf).into_string(),
+        TokenEncoding::Base32 => BASE32_NOPAD.encode(&buf),
+    };
+    Ok(token)
+}
+
+pub fn process_gen_uuid(version: UuidVersion) -> Result<String> {
+    let mut buf = [0u8; 16];
+    OsRng.fill_bytes(&mut buf);
+
+    match version {
+        UuidVersion::V4 => buf[6] = (buf[6] & 0x0f) | 0x40,
+        UuidVersion::V7 => {
+            buf[..6].copy_from_slice(&unix_millis()?.to_be_bytes()[2..]);
+            buf[6] = (buf[6] & 0x0f) | 0x70;
+        }
+    }
+    // RFC 9562 variant
+    buf[8] = (buf[8] & 0x3f) | 0x80;
+
+    let hex = HEXLOWER.encode(&buf);
+    Ok(format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    ))
+}
+
+/// 48 bit millisecond timestamp followed by 80 random bits, in Crockford base32
+pub fn process_gen_ulid() -> Result<String> {
+    let mut random = [0u8; 10];
+    OsRng.fill_bytes(&mut random);
+
+    let mut value = (unix_millis()? as u128) << 80;
+    for (i, b) in random.iter().enumerate() {
+        value |= (*b as u128) << (8 * (9 - i));
+    }
+
+    let ulid = (0..26)
+        .map(|i| CROCKFORD[((value >> (5 * (25 - i))) & 0x1f) as usize] as char)
+        .collect();
+    Ok(ulid)
+}
+
+pub fn process_gen_nanoid(size: usize, alphabet: &str) -> Result<String> {
+    let alphabet: Vec<char> = alphabet.chars().collect();
+    if alphabet.is_empty() {
+        anyhow::bail!("Alphabet must not be empty");
+    }
+
+    let mut rng = OsRng;
+    let id = (0..size)
+        .map(|_| {
+            *alphabet
+                .choose(&mut rng)
+                .expect("chars won't be empty in this context")
+        })
+        .collect();
+    Ok(id)
+}
+
+fn unix_millis() -> Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_token() -> Result<()> {
+        assert_eq!(process_gen_token(32, TokenEncoding::Hex)?.len(), 64);
+        assert_eq!(process_gen_token(32, TokenEncoding::Base64Url)?.len(), 43);
+        assert_eq!(process_gen_token(5, TokenEncoding::Base32)?.len(), 8);
+        assert!(process_gen_token(0, TokenEncoding::Base58).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_uuid() -> Result<()> {
+        let v4 = process_gen_uuid(UuidVersion::V4)?;
+        assert_eq!(v4.len(), 36);
+        assert_eq!(&v4[14..15], "4");
+        assert!("89ab".contains(&v4[19..20]));
+
+        let v7 = process_gen_uuid(UuidVersion::V7)?;
+        assert_eq!(&v7[14..15], "7");
+        Ok(())
+    }
+
+    #[test]
+    fn test_gen_ulid_and_nanoid() -> Result<()> {
+        let ulid = process_gen_ulid()?;
+        assert_eq!(ulid.len(), 26);
+        assert!(ulid.bytes().all(|b| CROCKFORD.contains(&b)));
+
+        let id = process_gen_nanoid(21, NANOID_ALPHABET)?;
+        assert_eq!(id.chars().count(), 21);
+        assert_eq!(process_gen_nanoid(8, "ab")?.replace(['a', 'b'], ""), "");
+        assert!(process_gen_nanoid(8, "").is_err());
+        Ok(())
+    }
+}
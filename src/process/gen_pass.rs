@@ -4,6 +4,11 @@ const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
 const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
 const NUMBER: &[u8] = b"1234567890";
 const SYMBOL: &[u8] = b"!@#$%^&*~,.;";
+const CONSONANT: &[u8] = b"bcdfghjklmnprstvwxz";
+const VOWEL: &[u8] = b"aeiou";
+
+/// Pattern used by `--pronounceable`
+pub const PRONOUNCEABLE_PATTERN: &str = "Cvccvc-Cvccvc-99";
 
 pub fn process_genpass(
     length: u8,
@@ -68,3 +73,65 @@ pub fn process_genpass(
     // eprintln!("Password strength: {}", estimate.score());
     Ok(password)
 }
+
+/// Generate a password from a template:
+/// `c`/`C` consonant, `v`/`V` vowel (upper case for capitals), `9` digit, `s` symbol.
+/// `\` escapes the next character, anything else is copied as is.
+pub fn process_genpass_pattern(pattern: &str) -> anyhow::Result<String> {
+    let mut rng = rand::thread_rng();
+    let mut password = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+
+    while let Some(c) = chars.next() {
+        let class = match c {
+            'c' | 'C' => CONSONANT,
+            'v' | 'V' => VOWEL,
+            '9' => NUMBER,
+            's' => SYMBOL,
+            '\\' => {
+                let literal = chars
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Pattern ends with a dangling escape"))?;
+                password.push(literal);
+                continue;
+            }
+            literal => {
+                password.push(literal);
+                continue;
+            }
+        };
+        let picked = *class
+            .choose(&mut rng)
+            .expect("chars won't be empty in this context") as char;
+        if c.is_ascii_uppercase() {
+            password.push(picked.to_ascii_uppercase());
+        } else {
+            password.push(picked);
+        }
+    }
+
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_genpass_pattern() -> anyhow::Result<()> {
+        let password = process_genpass_pattern("Cvccvc-99-s\\v")?;
+        let bytes = password.as_bytes();
+        assert_eq!(bytes.len(), 12);
+        assert!(CONSONANT.contains(&bytes[0].to_ascii_lowercase()));
+        assert!(bytes[0].is_ascii_uppercase());
+        assert!(VOWEL.contains(&bytes[1]));
+        assert!(CONSONANT.contains(&bytes[2]));
+        assert_eq!(bytes[6], b'-');
+        assert!(NUMBER.contains(&bytes[7]) && NUMBER.contains(&bytes[8]));
+        assert!(SYMBOL.contains(&bytes[10]));
+        assert_eq!(&password[11..], "v");
+
+        assert!(process_genpass_pattern("abc\\").is_err());
+        Ok(())
+    }
+}
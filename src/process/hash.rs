@@ -0,0 +1,218 @@
+use std::{
+    fs::{self, File},
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use anyhow::Result;
+use data_encoding::HEXLOWER;
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{cli::HashAlgo, utils::get_reader};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CheckStatus {
+    Ok,
+    Failed,
+    /// Could not be opened or read
+    Missing,
+}
+
+enum Hasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(Sha256),
+    Sha512(Sha512),
+    Sha1(Sha1),
+    Md5(Md5),
+    Xxh3(Box<Xxh3>),
+}
+
+/// Hash every input, `-` being stdin. Directories are walked recursively in sorted order,
+/// so hashing a directory produces a manifest.
+/// Returns `(path, hex digest)` pairs in `sha256sum` order.
+pub fn process_hash(inputs: &[String], algo: HashAlgo) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input != "-" && Path::new(input).is_dir() {
+            collect_files(Path::new(input), &mut files)?;
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    files
+        .into_iter()
+        .map(|file| {
+            let mut reader = get_reader(&file)?;
+            let digest = hash_reader(&mut reader, algo)?;
+            Ok((file, digest))
+        })
+        .collect()
+}
+
+/// Verify a `sha256sum` style file: `<hex>  <path>` per line, `*` before the path is allowed
+pub fn process_hash_check(sums: &str, algo: HashAlgo) -> Result<Vec<(String, CheckStatus)>> {
+    let reader = BufReader::new(get_reader(sums)?);
+    let mut results = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (expected, path) = line
+            .split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("{}:{}: malformed checksum line", sums, i + 1))?;
+        let path = path.strip_prefix([' ', '*']).unwrap_or(path);
+
+        let digest = File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut file| hash_reader(&mut file, algo));
+        let status = match digest {
+            Ok(digest) if digest.eq_ignore_ascii_case(expected) => CheckStatus::Ok,
+            Ok(_) => CheckStatus::Failed,
+            Err(_) => CheckStatus::Missing,
+        };
+        results.push((path.to_string(), status));
+    }
+    Ok(results)
+}
+
+/// Stream the reader through the hash and return the lowercase hex digest
+pub fn hash_reader(reader: &mut dyn Read, algo: HashAlgo) -> Result<String> {
+    let mut hasher = Hasher::new(algo);
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(HEXLOWER.encode(&hasher.finalize()))
+}
+
+/// Files under `dir` in sorted order. Symlinks to files are kept, symlinks to
+/// directories are skipped so a link back up the tree cannot loop forever.
+pub fn collect_files(dir: &Path, files: &mut Vec<String>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+    for path in entries {
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if !(file_type.is_symlink() && path.is_dir()) {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(())
+}
+
+impl Hasher {
+    fn new(algo: HashAlgo) -> Self {
+        match algo {
+            HashAlgo::Blake3 => Hasher::Blake3(Box::default()),
+            HashAlgo::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgo::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgo::Sha1 => Hasher::Sha1(Sha1::new()),
+            HashAlgo::Md5 => Hasher::Md5(Md5::new()),
+            HashAlgo::Xxh3 => Hasher::Xxh3(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Blake3(h) => {
+                h.update(data);
+            }
+            Hasher::Sha256(h) => h.update(data),
+            Hasher::Sha512(h) => h.update(data),
+            Hasher::Sha1(h) => h.update(data),
+            Hasher::Md5(h) => h.update(data),
+            Hasher::Xxh3(h) => h.update(data),
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        match self {
+            Hasher::Blake3(h) => h.finalize().as_bytes().to_vec(),
+            Hasher::Sha256(h) => h.finalize().to_vec(),
+            Hasher::Sha512(h) => h.finalize().to_vec(),
+            Hasher::Sha1(h) => h.finalize().to_vec(),
+            Hasher::Md5(h) => h.finalize().to_vec(),
+            Hasher::Xxh3(h) => h.digest().to_be_bytes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_known_vectors() -> Result<()> {
+        let cases = [
+            (
+                HashAlgo::Blake3,
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+            (
+                HashAlgo::Sha256,
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (HashAlgo::Sha1, "a9993e364706816aba3e25717850c26c9cd0d89d"),
+            (HashAlgo::Md5, "900150983cd24fb0d6963f7d28e17f72"),
+            (HashAlgo::Xxh3, "78af5f94892f3950"),
+        ];
+        for (algo, expected) in cases {
+            assert_eq!(hash_reader(&mut &b"abc"[..], algo)?, expected, "{}", algo);
+        }
+        assert!(hash_reader(&mut &b"abc"[..], HashAlgo::Sha512)?.starts_with("ddaf35a193617aba"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_dir_and_check() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("b.txt"), "b")?;
+        fs::write(dir.join("sub/a.txt"), "a")?;
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(dir, dir.join("sub/loop"))?;
+
+        let manifest = process_hash(&[dir.to_string_lossy().into_owned()], HashAlgo::Sha256)?;
+        assert_eq!(manifest.len(), 2);
+        assert!(manifest[0].0.ends_with("b.txt"));
+        assert!(manifest[1].0.ends_with("a.txt"));
+
+        let sums: String = manifest
+            .iter()
+            .map(|(path, digest)| format!("{}  {}\n", digest, path))
+            .collect();
+        let sums_path = dir.join("SUMS");
+        fs::write(&sums_path, sums)?;
+        fs::write(dir.join("b.txt"), "changed")?;
+        fs::remove_file(dir.join("sub/a.txt"))?;
+
+        // unreadable entries are reported rather than aborting the check
+        fs::write(
+            &sums_path,
+            format!("{}  {}\n", manifest[0].1, dir.join("sub").display())
+                + &fs::read_to_string(&sums_path)?,
+        )?;
+        let results = process_hash_check(&sums_path.to_string_lossy(), HashAlgo::Sha256)?;
+        assert_eq!(results[0].1, CheckStatus::Missing);
+        assert_eq!(results[1].1, CheckStatus::Failed);
+        assert_eq!(results[2].1, CheckStatus::Missing);
+
+        Ok(())
+    }
+}
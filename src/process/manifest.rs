@@ -0,0 +1,200 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::{self, File},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+
+use super::hash::{collect_files, hash_reader};
+use crate::cli::HashAlgo;
+
+/// First line of a directory manifest
+const MANIFEST_HEADER: &str = "rcli manifest v1";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ManifestStatus {
+    Ok,
+    Modified,
+    /// Listed in the manifest but not on disk
+    Missing,
+    /// On disk but not listed in the manifest
+    Extra,
+}
+
+impl fmt::Display for ManifestStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            ManifestStatus::Ok => "OK",
+            ManifestStatus::Modified => "MODIFIED",
+            ManifestStatus::Missing => "MISSING",
+            ManifestStatus::Extra => "EXTRA",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+/// One file of a manifest, `path` relative to the directory with `/` separators
+#[derive(Debug, PartialEq)]
+struct ManifestEntry {
+    path: String,
+    size: u64,
+    /// blake3 hex digest
+    hash: String,
+}
+
+/// Hash every file under `dir` with blake3 into a manifest of `<hash>  <size>  <path>`
+/// lines sorted by path. Files in `skip`, such as the manifest itself, are left out.
+pub fn process_dir_manifest(dir: &Path, skip: &[&Path]) -> Result<String> {
+    let mut out = format!("{}\n", MANIFEST_HEADER);
+    for (path, file) in dir_files(dir, skip)? {
+        let mut reader = File::open(&file)?;
+        let size = reader.metadata()?.len();
+        let hash = hash_reader(&mut reader, HashAlgo::Blake3)?;
+        out.push_str(&format!("{}  {}  {}\n", hash, size, path));
+    }
+    Ok(out)
+}
+
+/// Compare `dir` against the manifest text, returning every file listed in the manifest
+/// or found on disk with its status, sorted by path
+pub fn process_dir_manifest_check(
+    dir: &Path,
+    manifest: &str,
+    skip: &[&Path],
+) -> Result<Vec<(String, ManifestStatus)>> {
+    let mut on_disk = dir_files(dir, skip)?;
+    let mut results = Vec::new();
+    for entry in parse_manifest(manifest)? {
+        let status = match on_disk.remove(&entry.path) {
+            None => ManifestStatus::Missing,
+            Some(file) => {
+                let mut reader = File::open(&file)?;
+                if reader.metadata()?.len() == entry.size
+                    && hash_reader(&mut reader, HashAlgo::Blake3)?.eq_ignore_ascii_case(&entry.hash)
+                {
+                    ManifestStatus::Ok
+                } else {
+                    ManifestStatus::Modified
+                }
+            }
+        };
+        results.push((entry.path, status));
+    }
+    results.extend(
+        on_disk
+            .into_keys()
+            .map(|path| (path, ManifestStatus::Extra)),
+    );
+    results.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(results)
+}
+
+fn parse_manifest(manifest: &str) -> Result<Vec<ManifestEntry>> {
+    let mut lines = manifest.lines();
+    if lines.next().map(str::trim) != Some(MANIFEST_HEADER) {
+        anyhow::bail!(
+            "Not an rcli manifest, expected it to start with '{}'",
+            MANIFEST_HEADER
+        );
+    }
+    lines
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let malformed = || anyhow::anyhow!("manifest:{}: malformed line", i + 2);
+            let mut parts = line.splitn(3, "  ");
+            let (Some(hash), Some(size), Some(path)) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(malformed());
+            };
+            Ok(ManifestEntry {
+                path: path.to_string(),
+                size: size.parse().map_err(|_| malformed())?,
+                hash: hash.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Files under `dir` keyed by their manifest path
+fn dir_files(dir: &Path, skip: &[&Path]) -> Result<BTreeMap<String, PathBuf>> {
+    let skip = skip.iter().filter_map(|p| canonical(p)).collect::<Vec<_>>();
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+
+    let mut out = BTreeMap::new();
+    for file in files.into_iter().map(PathBuf::from) {
+        if canonical(&file).is_some_and(|f| skip.contains(&f)) {
+            continue;
+        }
+        let path = file
+            .strip_prefix(dir)?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.contains(['\n', '\r']) {
+            anyhow::bail!("Cannot list {} in a manifest", file.display());
+        }
+        out.insert(path, file);
+    }
+    Ok(out)
+}
+
+/// The canonical form of `path`, which need not exist yet as long as its parent does
+fn canonical(path: &Path) -> Option<PathBuf> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(parent).ok()?.join(path.file_name()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_manifest_check() -> Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let dir = tmp.path();
+        fs::create_dir_all(dir.join("sub dir"))?;
+        fs::write(dir.join("a.txt"), "hello")?;
+        fs::write(dir.join("sub dir/b.txt"), "world")?;
+        fs::write(dir.join("c.txt"), "gone soon")?;
+        let manifest_path = dir.join("MANIFEST");
+        fs::write(&manifest_path, "stale")?;
+
+        let manifest = process_dir_manifest(dir, &[&manifest_path])?;
+        assert_eq!(
+            manifest.lines().nth(1),
+            Some("ea8f163db38682925e4491c5e58d4bb3506ef8c14eb78a86e908c5624a67200f  5  a.txt")
+        );
+        assert!(manifest.ends_with("  5  sub dir/b.txt\n"));
+        assert_eq!(manifest.lines().count(), 4);
+
+        let results = process_dir_manifest_check(dir, &manifest, &[&manifest_path])?;
+        assert!(results.iter().all(|(_, s)| *s == ManifestStatus::Ok));
+
+        fs::write(dir.join("a.txt"), "HELLO")?;
+        fs::remove_file(dir.join("c.txt"))?;
+        fs::write(dir.join("d.txt"), "new")?;
+        let results = process_dir_manifest_check(dir, &manifest, &[&manifest_path])?;
+        assert_eq!(
+            results,
+            vec![
+                ("a.txt".to_string(), ManifestStatus::Modified),
+                ("c.txt".to_string(), ManifestStatus::Missing),
+                ("d.txt".to_string(), ManifestStatus::Extra),
+                ("sub dir/b.txt".to_string(), ManifestStatus::Ok),
+            ]
+        );
+
+        assert!(process_dir_manifest_check(dir, "hello\n", &[]).is_err());
+        let bad = format!("{}\nabc  xyz  a.txt\n", MANIFEST_HEADER);
+        assert!(process_dir_manifest_check(dir, &bad, &[]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,402 @@
+//! The minisign key and signature file formats. signify public keys and
+//! signatures share the same layout, without the trusted comment.
+
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use blake2::{Blake2b512, Digest};
+use ed25519_dalek::{Signature, SigningKey, VerifyingKey};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use super::keys::PassphraseSource;
+
+pub const UNTRUSTED_COMMENT: &str = "untrusted comment: ";
+const TRUSTED_COMMENT: &str = "trusted comment: ";
+
+/// Pure Ed25519 over the message, as signify and minisign before 0.10 sign
+pub const ALG_LEGACY: [u8; 2] = *b"Ed";
+/// Ed25519 over the BLAKE2b-512 of the message
+pub const ALG_HASHED: [u8; 2] = *b"ED";
+const KDF_SCRYPT: [u8; 2] = *b"Sc";
+const KDF_NONE: [u8; 2] = [0, 0];
+const CHECKSUM_BLAKE2B: [u8; 2] = *b"B2";
+
+/// libsodium's scrypt "sensitive" limits, minisign's defaults
+const KDF_OPSLIMIT: u64 = 33_554_432;
+const KDF_MEMLIMIT: u64 = 1_073_741_824;
+
+const KEY_ID_LEN: usize = 8;
+/// key id, the 64 byte Ed25519 secret key and a checksum
+const KEYNUM_SK_LEN: usize = KEY_ID_LEN + 64 + 32;
+const SECRET_KEY_LEN: usize = 2 + 2 + 2 + 32 + 8 + 8 + KEYNUM_SK_LEN;
+
+pub struct MinisignPublicKey {
+    pub key_id: [u8; KEY_ID_LEN],
+    pub key: VerifyingKey,
+}
+
+pub struct MinisignSecretKey {
+    pub key_id: [u8; KEY_ID_LEN],
+    pub key: SigningKey,
+}
+
+pub struct MinisignSignature {
+    pub untrusted_comment: String,
+    pub algorithm: [u8; 2],
+    pub key_id: [u8; KEY_ID_LEN],
+    pub signature: Signature,
+    /// Missing in signify signatures
+    pub trusted_comment: Option<String>,
+    pub global_signature: Option<Signature>,
+}
+
+/// The key id as minisign prints it
+pub fn key_id_hex(key_id: &[u8; KEY_ID_LEN]) -> String {
+    format!("{:016X}", u64::from_le_bytes(*key_id))
+}
+
+pub fn is_minisign(data: &[u8]) -> bool {
+    data.starts_with(UNTRUSTED_COMMENT.as_bytes())
+}
+
+/// The bytes `ALG_HASHED` signatures sign
+pub fn prehash(reader: &mut dyn std::io::Read) -> Result<[u8; 64]> {
+    let mut hasher = Blake2b512::new();
+    std::io::copy(reader, &mut hasher)?;
+    Ok(hasher.finalize().into())
+}
+
+impl MinisignPublicKey {
+    /// The public key file, or just its base64 line as `minisign -P` takes it
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let bin = decode_payload(data, "public key")?;
+        let bin: [u8; 2 + KEY_ID_LEN + 32] = bin.as_slice().try_into().map_err(|_| {
+            anyhow::anyhow!("minisign public key must be 42 bytes, got {}", bin.len())
+        })?;
+        if bin[..2] != ALG_LEGACY {
+            anyhow::bail!("Unsupported minisign public key algorithm");
+        }
+        Ok(Self {
+            key_id: bin[2..10].try_into()?,
+            key: VerifyingKey::from_bytes(bin[10..].try_into()?)?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bin = ALG_LEGACY.to_vec();
+        bin.extend_from_slice(&self.key_id);
+        bin.extend_from_slice(self.key.as_bytes());
+        format!(
+            "{}minisign public key {}\n{}\n",
+            UNTRUSTED_COMMENT,
+            key_id_hex(&self.key_id),
+            STANDARD.encode(bin)
+        )
+        .into_bytes()
+    }
+}
+
+impl MinisignSecretKey {
+    pub fn generate() -> Self {
+        let mut key_id = [0u8; KEY_ID_LEN];
+        OsRng.fill_bytes(&mut key_id);
+        Self {
+            key_id,
+            key: SigningKey::generate(&mut OsRng),
+        }
+    }
+
+    pub fn public_key(&self) -> MinisignPublicKey {
+        MinisignPublicKey {
+            key_id: self.key_id,
+            key: self.key.verifying_key(),
+        }
+    }
+
+    /// Unlocks keys encrypted with scrypt using a passphrase from `passphrase`
+    pub fn decode(data: &[u8], passphrase: &PassphraseSource) -> Result<Self> {
+        Self::decode_with(data, || passphrase.read(false))
+    }
+
+    /// As [`MinisignSecretKey::decode`], calling `passphrase` only for encrypted keys
+    pub fn decode_with(
+        data: &[u8],
+        passphrase: impl FnOnce() -> Result<Zeroizing<String>>,
+    ) -> Result<Self> {
+        let bin = Zeroizing::new(decode_payload(data, "secret key")?);
+        if bin.len() != SECRET_KEY_LEN {
+            anyhow::bail!(
+                "minisign secret key must be {} bytes, got {}",
+                SECRET_KEY_LEN,
+                bin.len()
+            );
+        }
+        if bin[..2] != ALG_LEGACY || bin[4..6] != CHECKSUM_BLAKE2B {
+            anyhow::bail!("Unsupported minisign secret key algorithm");
+        }
+
+        let mut keynum_sk = Zeroizing::new(bin[54..].to_vec());
+        match [bin[2], bin[3]] {
+            KDF_NONE => {}
+            KDF_SCRYPT => {
+                let opslimit = u64::from_le_bytes(bin[38..46].try_into()?);
+                let memlimit = u64::from_le_bytes(bin[46..54].try_into()?);
+                // minisign always writes its defaults, anything above is not a real key
+                if opslimit > KDF_OPSLIMIT || memlimit > KDF_MEMLIMIT {
+                    anyhow::bail!(
+                        "minisign key derivation limits {}/{} exceed minisign's defaults",
+                        opslimit,
+                        memlimit
+                    );
+                }
+                let passphrase = passphrase()?;
+                let stream = kdf_stream(&passphrase, &bin[6..38], opslimit, memlimit)?;
+                keynum_sk
+                    .iter_mut()
+                    .zip(stream.iter())
+                    .for_each(|(b, k)| *b ^= k);
+            }
+            _ => anyhow::bail!("Unsupported minisign key derivation"),
+        }
+
+        let key_id: [u8; KEY_ID_LEN] = keynum_sk[..KEY_ID_LEN].try_into()?;
+        let secret = &keynum_sk[KEY_ID_LEN..KEY_ID_LEN + 64];
+        if checksum(&key_id, secret).as_slice() != &keynum_sk[KEY_ID_LEN + 64..] {
+            anyhow::bail!("Wrong passphrase or corrupted minisign secret key");
+        }
+        let key = SigningKey::from_keypair_bytes(secret.try_into()?)
+            .map_err(|_| anyhow::anyhow!("Invalid minisign secret key"))?;
+        Ok(Self { key_id, key })
+    }
+
+    /// With a passphrase the key is encrypted the way minisign does, with scrypt
+    /// at libsodium's sensitive limits (1 GiB of memory)
+    pub fn encode(&self, passphrase: Option<&str>) -> Result<Vec<u8>> {
+        let mut keynum_sk = Zeroizing::new(self.key_id.to_vec());
+        let secret = Zeroizing::new(self.key.to_keypair_bytes());
+        keynum_sk.extend_from_slice(secret.as_ref());
+        keynum_sk.extend_from_slice(&checksum(&self.key_id, secret.as_ref()));
+
+        let mut salt = [0u8; 32];
+        let (kdf, opslimit, memlimit) = match passphrase {
+            Some(passphrase) => {
+                OsRng.fill_bytes(&mut salt);
+                let stream = kdf_stream(passphrase, &salt, KDF_OPSLIMIT, KDF_MEMLIMIT)?;
+                keynum_sk
+                    .iter_mut()
+                    .zip(stream.iter())
+                    .for_each(|(b, k)| *b ^= k);
+                (KDF_SCRYPT, KDF_OPSLIMIT, KDF_MEMLIMIT)
+            }
+            None => (KDF_NONE, 0, 0),
+        };
+
+        let mut bin = Zeroizing::new(Vec::with_capacity(SECRET_KEY_LEN));
+        bin.extend_from_slice(&ALG_LEGACY);
+        bin.extend_from_slice(&kdf);
+        bin.extend_from_slice(&CHECKSUM_BLAKE2B);
+        bin.extend_from_slice(&salt);
+        bin.extend_from_slice(&opslimit.to_le_bytes());
+        bin.extend_from_slice(&memlimit.to_le_bytes());
+        bin.extend_from_slice(&keynum_sk);
+
+        let comment = match passphrase {
+            Some(_) => "minisign encrypted secret key",
+            None => "minisign secret key",
+        };
+        Ok(format!(
+            "{}{}\n{}\n",
+            UNTRUSTED_COMMENT,
+            comment,
+            STANDARD.encode(&bin)
+        )
+        .into_bytes())
+    }
+}
+
+impl MinisignSignature {
+    pub fn decode(text: &str) -> Result<Self> {
+        let mut lines = text.lines().map(str::trim_end);
+        let untrusted_comment = lines
+            .next()
+            .and_then(|l| l.strip_prefix(UNTRUSTED_COMMENT))
+            .ok_or_else(|| {
+                anyhow::anyhow!("minisign signature must start with an untrusted comment")
+            })?
+            .to_string();
+        let bin = STANDARD.decode(lines.next().unwrap_or_default())?;
+        let bin: [u8; 2 + KEY_ID_LEN + 64] = bin.as_slice().try_into().map_err(|_| {
+            anyhow::anyhow!("minisign signature must be 74 bytes, got {}", bin.len())
+        })?;
+        let algorithm = [bin[0], bin[1]];
+        if algorithm != ALG_LEGACY && algorithm != ALG_HASHED {
+            anyhow::bail!("Unsupported minisign signature algorithm");
+        }
+
+        let (trusted_comment, global_signature) = match lines.next() {
+            Some(line) => {
+                let comment = line
+                    .strip_prefix(TRUSTED_COMMENT)
+                    .ok_or_else(|| anyhow::anyhow!("Malformed minisign trusted comment"))?;
+                let global = STANDARD.decode(lines.next().unwrap_or_default())?;
+                let global = Signature::from_slice(&global)
+                    .map_err(|_| anyhow::anyhow!("minisign global signature must be 64 bytes"))?;
+                (Some(comment.to_string()), Some(global))
+            }
+            None => (None, None),
+        };
+
+        Ok(Self {
+            untrusted_comment,
+            algorithm,
+            key_id: bin[2..10].try_into()?,
+            signature: Signature::from_bytes(bin[10..].try_into()?),
+            trusted_comment,
+            global_signature,
+        })
+    }
+
+    pub fn encode(&self) -> String {
+        let mut bin = self.algorithm.to_vec();
+        bin.extend_from_slice(&self.key_id);
+        bin.extend_from_slice(&self.signature.to_bytes());
+        let mut out = format!(
+            "{}{}\n{}\n",
+            UNTRUSTED_COMMENT,
+            self.untrusted_comment,
+            STANDARD.encode(bin)
+        );
+        if let (Some(comment), Some(global)) = (&self.trusted_comment, &self.global_signature) {
+            out.push_str(&format!(
+                "{}{}\n{}\n",
+                TRUSTED_COMMENT,
+                comment,
+                STANDARD.encode(global.to_bytes())
+            ));
+        }
+        out
+    }
+
+    /// What the global signature covers: the signature and the trusted comment
+    pub fn global_message(signature: &Signature, trusted_comment: &str) -> Vec<u8> {
+        let mut message = signature.to_bytes().to_vec();
+        message.extend_from_slice(trusted_comment.as_bytes());
+        message
+    }
+}
+
+/// The base64 line of a key file, after the untrusted comment if there is one
+fn decode_payload(data: &[u8], what: &str) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| anyhow::anyhow!("minisign {} is not valid UTF-8", what))?;
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with(UNTRUSTED_COMMENT))
+        .ok_or_else(|| anyhow::anyhow!("minisign {} is empty", what))?;
+    STANDARD
+        .decode(line)
+        .map_err(|e| anyhow::anyhow!("Invalid minisign {}: {}", what, e))
+}
+
+fn checksum(key_id: &[u8; KEY_ID_LEN], secret: &[u8]) -> [u8; 32] {
+    let mut hasher = blake2::Blake2b::<blake2::digest::consts::U32>::new();
+    hasher.update(ALG_LEGACY);
+    hasher.update(key_id);
+    hasher.update(secret);
+    hasher.finalize().into()
+}
+
+/// crypto_pwhash_scryptsalsa208sha256 as minisign calls it
+fn kdf_stream(
+    passphrase: &str,
+    salt: &[u8],
+    opslimit: u64,
+    memlimit: u64,
+) -> Result<Zeroizing<Vec<u8>>> {
+    let (log_n, r, p) = scrypt_params(opslimit, memlimit);
+    // The output length comes from the buffer, Params only checks its own `len`
+    let params = scrypt::Params::new(log_n, r, p, scrypt::Params::RECOMMENDED_LEN)
+        .map_err(|e| anyhow::anyhow!("Invalid scrypt parameters: {}", e))?;
+    let mut stream = Zeroizing::new(vec![0u8; KEYNUM_SK_LEN]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut stream)
+        .map_err(|e| anyhow::anyhow!("Unable to derive the key: {}", e))?;
+    Ok(stream)
+}
+
+/// libsodium's pickparams: scrypt N, r and p from its ops and memory limits
+fn scrypt_params(opslimit: u64, memlimit: u64) -> (u8, u32, u32) {
+    let opslimit = opslimit.max(32_768);
+    let r = 8u32;
+    let log_n_below = |max_n: u64| (1..63u8).find(|&n| 1u64 << n > max_n / 2).unwrap_or(63);
+    if opslimit < memlimit / 32 {
+        let log_n = log_n_below(opslimit / (r as u64 * 4));
+        (log_n, r, 1)
+    } else {
+        let log_n = log_n_below(memlimit / (r as u64 * 128));
+        let max_rp = ((opslimit / 4) / (1u64 << log_n)).min(0x3fff_ffff);
+        (log_n, r, (max_rp as u32 / r).max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn test_minisign_secret_key_round_trip() -> Result<()> {
+        let sk = MinisignSecretKey::generate();
+        let file = sk.encode(None)?;
+        let decoded = MinisignSecretKey::decode(&file, &PassphraseSource::Prompt)?;
+        assert_eq!(decoded.key_id, sk.key_id);
+        assert_eq!(decoded.key.to_bytes(), sk.key.to_bytes());
+
+        let pk = MinisignPublicKey::decode(&sk.public_key().encode())?;
+        assert_eq!(pk.key, sk.key.verifying_key());
+        Ok(())
+    }
+
+    #[test]
+    fn test_minisign_secret_key_interop() -> Result<()> {
+        // minisign's own layout with low scrypt limits (N = 2^14, passphrase "rcli"),
+        // built independently with Python's hashlib and cryptography since `minisign -G`
+        // only writes its 1 GiB defaults
+        let passphrase = || Ok(Zeroizing::new("rcli".to_string()));
+        let sk = MinisignSecretKey::decode_with(&fs::read("fixtures/minisign.key")?, passphrase)?;
+        let pk = MinisignPublicKey::decode(&fs::read("fixtures/minisign.pub")?)?;
+        assert_eq!(key_id_hex(&sk.key_id), "8664462F1D6CE2A9");
+        assert_eq!(sk.key_id, pk.key_id);
+        assert_eq!(sk.key.verifying_key(), pk.key);
+
+        let wrong = || Ok(Zeroizing::new("hunter2".to_string()));
+        assert!(
+            MinisignSecretKey::decode_with(&fs::read("fixtures/minisign.key")?, wrong).is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_kdf_limits_are_capped() -> Result<()> {
+        let sk = MinisignSecretKey::generate();
+        let text = String::from_utf8(sk.encode(None)?)?;
+        let (comment, payload) = text.trim_end().split_once('\n').expect("two lines");
+        let mut bin = STANDARD.decode(payload)?;
+        bin[2..4].copy_from_slice(&KDF_SCRYPT);
+        bin[38..46].copy_from_slice(&(1u64 << 63).to_le_bytes());
+        let crafted = format!("{}\n{}\n", comment, STANDARD.encode(bin));
+
+        let passphrase = || Ok(Zeroizing::new("rcli".to_string()));
+        let err = MinisignSecretKey::decode_with(crafted.as_bytes(), passphrase).err();
+        assert!(err.is_some_and(|e| e.to_string().contains("exceed minisign's defaults")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_scrypt_params() {
+        // minisign's defaults need 1 GiB: N = 2^20, r = 8, p = 1
+        assert_eq!(scrypt_params(KDF_OPSLIMIT, KDF_MEMLIMIT), (20, 8, 1));
+        assert_eq!(scrypt_params(524_288, 16_777_216), (14, 8, 1));
+    }
+}
@@ -1,14 +1,48 @@
+mod auto_decode;
 mod b64;
+mod codec;
+mod compress;
+mod convert;
 mod csv_convert;
+mod data_uri;
+mod gen_id;
 mod gen_pass;
+mod hash;
 mod http_serve;
 mod jwt;
+mod keys;
+mod manifest;
+mod minisign;
+mod pass_check;
+mod query;
+mod signature;
 mod text;
 
-pub use b64::{process_decode, process_encode};
+pub use auto_decode::process_auto_decode;
+pub use b64::{process_decode, process_decode_reader, process_encode};
+pub use codec::{process_codec_decode, process_codec_encode};
+pub use compress::{
+    process_compress, process_decompress, process_decompress_reader, process_precompress_dir,
+    PrecompressStats,
+};
+pub use convert::process_convert;
 pub use csv_convert::process_csv;
-pub use gen_pass::process_genpass;
+pub use data_uri::{mime_extension, process_decode_data_uri, process_encode_data_uri};
+pub use gen_id::{
+    process_gen_nanoid, process_gen_token, process_gen_ulid, process_gen_uuid, NANOID_ALPHABET,
+};
+pub use gen_pass::{process_genpass, process_genpass_pattern, PRONOUNCEABLE_PATTERN};
+pub use hash::{process_hash, process_hash_check, CheckStatus};
 pub use http_serve::process_http_serve;
 pub use jwt::{process_jwt_sign, process_jwt_verify};
+pub use keys::PassphraseSource;
+pub use manifest::{process_dir_manifest, process_dir_manifest_check, ManifestStatus};
+pub use pass_check::process_genpass_check;
+pub use query::{process_query, process_set};
+pub use signature::SignatureFile;
 pub use text::{process_decrypt, process_encrypt};
 pub use text::{process_generate_key, process_text_sign, process_text_verify};
+pub use text::{process_key_convert, process_key_info, process_key_pub, KeyInfo};
+pub use text::{
+    process_key_format, process_text_sign_file, process_text_verify_data, process_text_verify_file,
+};
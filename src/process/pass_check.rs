@@ -0,0 +1,204 @@
+use std::{
+    cmp::Ordering,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::Path,
+};
+
+use anyhow::Result;
+use data_encoding::HEXUPPER;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use zxcvbn::zxcvbn;
+
+use crate::utils::get_reader;
+
+#[derive(Debug, Serialize)]
+pub struct PasswordReport {
+    pub password: String,
+    pub score: u8,
+    pub guesses: u64,
+    pub crack_times: CrackTimesReport,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    /// How often the password appears in the breach database, if one was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub breach_count: Option<u64>,
+}
+
+/// Human readable crack time estimates for the zxcvbn attack scenarios
+#[derive(Debug, Serialize)]
+pub struct CrackTimesReport {
+    pub online_throttling: String,
+    pub online_no_throttling: String,
+    pub offline_slow_hashing: String,
+    pub offline_fast_hashing: String,
+}
+
+/// A local Have I Been Pwned dump, ordered by hash, with `SHA1:COUNT` lines
+pub struct BreachDb {
+    reader: BufReader<File>,
+    len: u64,
+}
+
+/// Score every non-empty line of the input with zxcvbn,
+/// and look it up in the breach database if there is one
+pub fn process_genpass_check(
+    input: &str,
+    user_inputs: &[String],
+    breach_db: Option<&Path>,
+) -> Result<Vec<PasswordReport>> {
+    let reader = BufReader::new(get_reader(input)?);
+    let user_inputs: Vec<&str> = user_inputs.iter().map(|s| s.as_str()).collect();
+    let mut breach_db = breach_db.map(BreachDb::open).transpose()?;
+
+    let mut reports = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let password = line.trim_end_matches('\r');
+        if password.is_empty() {
+            continue;
+        }
+        let mut report = check_password(password, &user_inputs)?;
+        if let Some(db) = breach_db.as_mut() {
+            report.breach_count = Some(db.lookup(password)?.unwrap_or(0));
+        }
+        reports.push(report);
+    }
+    Ok(reports)
+}
+
+impl BreachDb {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            reader: BufReader::new(file),
+            len,
+        })
+    }
+
+    /// Binary search the file for the password's SHA-1 and return its count.
+    /// Only the lines visited by the search are read.
+    pub fn lookup(&mut self, password: &str) -> Result<Option<u64>> {
+        let target = HEXUPPER.encode(&Sha1::digest(password.as_bytes()));
+
+        // invariant: `lo` is the start of a line and the match, if any, starts in [lo, hi)
+        let (mut lo, mut hi) = (0, self.len);
+        let mut line = String::new();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let start = self.line_start_from(mid)?;
+            if start >= hi {
+                hi = mid;
+                continue;
+            }
+
+            line.clear();
+            let n = self.reader.read_line(&mut line)? as u64;
+            let (hash, count) = line
+                .trim_end()
+                .split_once(':')
+                .unwrap_or((line.trim_end(), ""));
+            match hash.to_ascii_uppercase().as_str().cmp(&target) {
+                Ordering::Equal => return Ok(Some(count.parse().unwrap_or(1))),
+                Ordering::Less => lo = start + n,
+                Ordering::Greater => hi = mid,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Position the reader at the first line starting at or after `pos`
+    fn line_start_from(&mut self, pos: u64) -> Result<u64> {
+        if pos == 0 {
+            self.reader.seek(SeekFrom::Start(0))?;
+            return Ok(0);
+        }
+        self.reader.seek(SeekFrom::Start(pos - 1))?;
+        let mut skipped = Vec::new();
+        let n = self.reader.read_until(b'\n', &mut skipped)? as u64;
+        Ok(pos - 1 + n)
+    }
+}
+
+fn check_password(password: &str, user_inputs: &[&str]) -> Result<PasswordReport> {
+    let estimate = zxcvbn(password, user_inputs)?;
+    let crack_times = estimate.crack_times();
+    let (warning, suggestions) = match estimate.feedback() {
+        Some(feedback) => (
+            feedback.warning().map(|w| w.to_string()),
+            feedback
+                .suggestions()
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+        ),
+        None => (None, vec![]),
+    };
+
+    Ok(PasswordReport {
+        password: password.to_string(),
+        score: estimate.score(),
+        guesses: estimate.guesses(),
+        crack_times: CrackTimesReport {
+            online_throttling: crack_times.online_throttling_100_per_hour().to_string(),
+            online_no_throttling: crack_times.online_no_throttling_10_per_second().to_string(),
+            offline_slow_hashing: crack_times
+                .offline_slow_hashing_1e4_per_second()
+                .to_string(),
+            offline_fast_hashing: crack_times
+                .offline_fast_hashing_1e10_per_second()
+                .to_string(),
+        },
+        warning,
+        suggestions,
+        breach_count: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_password_score() -> Result<()> {
+        let weak = check_password("password", &[])?;
+        assert_eq!(weak.score, 0);
+        assert!(weak.warning.is_some());
+
+        let strong = check_password("r0sebudmaelstrom11/20/91aaaa", &[])?;
+        assert_eq!(strong.score, 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_password_user_inputs() -> Result<()> {
+        let without = check_password("zorblaxquint1865", &[])?;
+        let with = check_password("zorblaxquint1865", &["zorblaxquint"])?;
+        assert!(with.guesses < without.guesses);
+        Ok(())
+    }
+
+    #[test]
+    fn test_breach_db_lookup() -> Result<()> {
+        let mut lines: Vec<String> = ["password", "123456", "qwerty", "letmein", "dragon"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| format!("{}:{}\r\n", HEXUPPER.encode(&Sha1::digest(p)), i + 1))
+            .collect();
+        lines.sort();
+        let file = tempfile::NamedTempFile::new()?;
+        std::fs::write(file.path(), lines.concat())?;
+
+        let mut db = BreachDb::open(file.path())?;
+        assert_eq!(db.lookup("password")?, Some(1));
+        assert_eq!(db.lookup("dragon")?, Some(5));
+        for p in ["123456", "qwerty", "letmein"] {
+            assert!(db.lookup(p)?.is_some());
+        }
+        assert_eq!(db.lookup("zorblaxquint1865")?, None);
+
+        Ok(())
+    }
+}
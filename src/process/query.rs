@@ -0,0 +1,665 @@
+use std::{cmp::Ordering, io::Read, iter::Peekable, str::Chars};
+
+use anyhow::Result;
+use serde_json::{Map, Value};
+
+use crate::{cli::OutputFormat, utils::get_reader};
+
+use super::convert::{format_from_path, parse, serialize};
+
+/// A parsed filter from the jq subset understood by `rcli query`:
+/// `.`, `.a.b`, `.["a"]`, `.[0]`, `.[-1]`, `.[]`, `|`, `,`, literals,
+/// `== != < <= > >=`, `and`, `or`, `not`, `select(f)`, `length` and `keys`.
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Identity,
+    Literal(Value),
+    Field(Box<Filter>, String),
+    Index(Box<Filter>, i64),
+    Iterate(Box<Filter>),
+    Pipe(Box<Filter>, Box<Filter>),
+    Comma(Box<Filter>, Box<Filter>),
+    Compare(Box<Filter>, CompareOp, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Select(Box<Filter>),
+    Not,
+    Length,
+    Keys,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Pipe,
+    Comma,
+    Op(CompareOp),
+    Ident(String),
+    Literal(Value),
+}
+
+/// One step of a `set` path: `server.port`, `servers[0].host`
+#[derive(Debug, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// Run a jq-style filter over a JSON, YAML or TOML document and serialize every result
+/// in `to`. With `raw`, string results are printed without quotes.
+pub fn process_query(
+    input: &str,
+    from: Option<OutputFormat>,
+    filter: &str,
+    to: OutputFormat,
+    compact: bool,
+    raw: bool,
+) -> Result<String> {
+    let value = read_document(input, from)?;
+    let mut output = String::new();
+    for (i, result) in query(&value, filter)?.iter().enumerate() {
+        match result {
+            Value::String(s) if raw => {
+                output.push_str(s);
+                output.push('\n');
+            }
+            result => {
+                if i > 0 && matches!(to, OutputFormat::Yaml) {
+                    output.push_str("---\n");
+                }
+                output.push_str(&serialize(result, to, compact)?);
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Apply `path=value` assignments to a document and return it re-serialized in its own format,
+/// or in `to`. The value is parsed as JSON when possible and used as a string otherwise.
+pub fn process_set(
+    input: &str,
+    from: Option<OutputFormat>,
+    to: Option<OutputFormat>,
+    assignments: &[String],
+) -> Result<String> {
+    let from = match from {
+        Some(from) => from,
+        None => format_from_path(input)?,
+    };
+    let mut value = read_document(input, Some(from))?;
+    for assignment in assignments {
+        let (path, raw) = assignment
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Expected path=value, got {}", assignment))?;
+        let new_value = serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.into()));
+        set_path(&mut value, path, new_value)?;
+    }
+    serialize(&value, to.unwrap_or(from), false)
+}
+
+fn read_document(input: &str, from: Option<OutputFormat>) -> Result<Value> {
+    let from = match from {
+        Some(from) => from,
+        None => format_from_path(input)?,
+    };
+    let mut reader = get_reader(input)?;
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    let name = if input == "-" { "<stdin>" } else { input };
+    parse(&content, from).map_err(|e| anyhow::anyhow!("{}: invalid {}: {}", name, from, e))
+}
+
+pub fn query(value: &Value, filter: &str) -> Result<Vec<Value>> {
+    let tokens = tokenize(filter)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let filter = parser.parse_pipe()?;
+    if let Some(token) = parser.tokens.get(parser.pos) {
+        anyhow::bail!("Unexpected {:?} in filter", token);
+    }
+    eval(&filter, value)
+}
+
+fn tokenize(filter: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = filter.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        let token = match c {
+            c if c.is_whitespace() => {
+                chars.next();
+                continue;
+            }
+            '.' => single(&mut chars, Token::Dot),
+            '[' => single(&mut chars, Token::LBracket),
+            ']' => single(&mut chars, Token::RBracket),
+            '(' => single(&mut chars, Token::LParen),
+            ')' => single(&mut chars, Token::RParen),
+            '|' => single(&mut chars, Token::Pipe),
+            ',' => single(&mut chars, Token::Comma),
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let eq = chars.next_if_eq(&'=').is_some();
+                let op = match (c, eq) {
+                    ('=', true) => CompareOp::Eq,
+                    ('!', true) => CompareOp::Ne,
+                    ('<', false) => CompareOp::Lt,
+                    ('<', true) => CompareOp::Le,
+                    ('>', false) => CompareOp::Gt,
+                    ('>', true) => CompareOp::Ge,
+                    _ => anyhow::bail!("Unsupported operator {} in filter", c),
+                };
+                Token::Op(op)
+            }
+            '"' => {
+                let mut literal = String::from(chars.next().unwrap_or('"'));
+                let mut escaped = false;
+                loop {
+                    let c = chars
+                        .next()
+                        .ok_or_else(|| anyhow::anyhow!("Unterminated string in filter"))?;
+                    literal.push(c);
+                    match c {
+                        '"' if !escaped => break,
+                        '\\' => escaped = !escaped,
+                        _ => escaped = false,
+                    }
+                }
+                Token::Literal(serde_json::from_str(&literal)?)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let number = take_while(&mut chars, |c| {
+                    c.is_ascii_alphanumeric() || matches!(c, '-' | '+' | '.')
+                });
+                Token::Literal(
+                    serde_json::from_str(&number)
+                        .map_err(|_| anyhow::anyhow!("Invalid number {} in filter", number))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let ident = take_while(&mut chars, |c| c.is_alphanumeric() || c == '_');
+                match ident.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    "null" => Token::Literal(Value::Null),
+                    _ => Token::Ident(ident),
+                }
+            }
+            c => anyhow::bail!("Unexpected character {:?} in filter", c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+fn single(chars: &mut Peekable<Chars>, token: Token) -> Token {
+    chars.next();
+    token
+}
+
+fn take_while(chars: &mut Peekable<Chars>, f: impl Fn(char) -> bool) -> String {
+    let mut s = String::new();
+    while let Some(c) = chars.next_if(|&c| f(c)) {
+        s.push(c);
+    }
+    s
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => anyhow::bail!("Expected {:?} in filter, found {:?}", expected, token),
+            None => anyhow::bail!("Expected {:?} at the end of the filter", expected),
+        }
+    }
+
+    fn eat_ident(&mut self, name: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(ident)) if ident == name) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_pipe(&mut self) -> Result<Filter> {
+        let mut left = self.parse_comma()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.pos += 1;
+            left = Filter::Pipe(Box::new(left), Box::new(self.parse_comma()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comma(&mut self) -> Result<Filter> {
+        let mut left = self.parse_or()?;
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            left = Filter::Comma(Box::new(left), Box::new(self.parse_or()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter> {
+        let mut left = self.parse_and()?;
+        while self.eat_ident("or") {
+            left = Filter::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter> {
+        let mut left = self.parse_compare()?;
+        while self.eat_ident("and") {
+            left = Filter::And(Box::new(left), Box::new(self.parse_compare()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_compare(&mut self) -> Result<Filter> {
+        let left = self.parse_postfix()?;
+        if let Some(&Token::Op(op)) = self.peek() {
+            self.pos += 1;
+            let right = self.parse_postfix()?;
+            return Ok(Filter::Compare(Box::new(left), op, Box::new(right)));
+        }
+        Ok(left)
+    }
+
+    fn parse_postfix(&mut self) -> Result<Filter> {
+        let mut filter = self.parse_primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Dot) => {
+                    self.pos += 1;
+                    match self.next() {
+                        Some(Token::Ident(name)) => {
+                            filter = Filter::Field(Box::new(filter), name);
+                        }
+                        Some(Token::Literal(Value::String(name))) => {
+                            filter = Filter::Field(Box::new(filter), name);
+                        }
+                        Some(Token::LBracket) => {
+                            self.pos -= 1;
+                        }
+                        _ => anyhow::bail!("Expected a field name after '.' in filter"),
+                    }
+                }
+                Some(Token::LBracket) => {
+                    self.pos += 1;
+                    filter = self.parse_bracket(filter)?;
+                }
+                _ => return Ok(filter),
+            }
+        }
+    }
+
+    /// `[]`, `[0]`, `[-1]` or `["key"]`, the opening bracket already consumed
+    fn parse_bracket(&mut self, filter: Filter) -> Result<Filter> {
+        let filter = match self.next() {
+            Some(Token::RBracket) => return Ok(Filter::Iterate(Box::new(filter))),
+            Some(Token::Literal(Value::String(name))) => Filter::Field(Box::new(filter), name),
+            Some(Token::Literal(Value::Number(n))) => {
+                let index = n
+                    .as_i64()
+                    .ok_or_else(|| anyhow::anyhow!("Array index {} is not an integer", n))?;
+                Filter::Index(Box::new(filter), index)
+            }
+            _ => anyhow::bail!("Expected ], an index or a quoted key after ["),
+        };
+        self.expect(Token::RBracket)?;
+        Ok(filter)
+    }
+
+    fn parse_primary(&mut self) -> Result<Filter> {
+        match self.next() {
+            Some(Token::Dot) => match self.peek() {
+                Some(Token::Ident(_)) | Some(Token::Literal(Value::String(_))) => {
+                    // `.name` is a field of the input, let the postfix loop handle it
+                    self.pos -= 1;
+                    Ok(Filter::Identity)
+                }
+                _ => Ok(Filter::Identity),
+            },
+            Some(Token::Literal(value)) => Ok(Filter::Literal(value)),
+            Some(Token::LParen) => {
+                let filter = self.parse_pipe()?;
+                self.expect(Token::RParen)?;
+                Ok(filter)
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "select" => {
+                    self.expect(Token::LParen)?;
+                    let filter = self.parse_pipe()?;
+                    self.expect(Token::RParen)?;
+                    Ok(Filter::Select(Box::new(filter)))
+                }
+                "not" => Ok(Filter::Not),
+                "length" => Ok(Filter::Length),
+                "keys" => Ok(Filter::Keys),
+                v => anyhow::bail!("Unsupported function {} in filter", v),
+            },
+            Some(token) => anyhow::bail!("Unexpected {:?} in filter", token),
+            None => anyhow::bail!("Filter ended unexpectedly"),
+        }
+    }
+}
+
+fn eval(filter: &Filter, input: &Value) -> Result<Vec<Value>> {
+    let results = match filter {
+        Filter::Identity => vec![input.clone()],
+        Filter::Literal(value) => vec![value.clone()],
+        Filter::Field(inner, name) => eval(inner, input)?
+            .into_iter()
+            .map(|value| match value {
+                Value::Object(mut map) => Ok(map.remove(name).unwrap_or(Value::Null)),
+                Value::Null => Ok(Value::Null),
+                v => anyhow::bail!("Cannot index {} with \"{}\"", type_name(&v), name),
+            })
+            .collect::<Result<_>>()?,
+        Filter::Index(inner, index) => eval(inner, input)?
+            .into_iter()
+            .map(|value| match value {
+                Value::Array(items) => {
+                    let len = items.len() as i64;
+                    let index = if *index < 0 { len + index } else { *index };
+                    Ok(usize::try_from(index)
+                        .ok()
+                        .and_then(|i| items.get(i).cloned())
+                        .unwrap_or(Value::Null))
+                }
+                Value::Null => Ok(Value::Null),
+                v => anyhow::bail!("Cannot index {} with a number", type_name(&v)),
+            })
+            .collect::<Result<_>>()?,
+        Filter::Iterate(inner) => {
+            let mut results = Vec::new();
+            for value in eval(inner, input)? {
+                match value {
+                    Value::Array(items) => results.extend(items),
+                    Value::Object(map) => results.extend(map.into_iter().map(|(_, v)| v)),
+                    v => anyhow::bail!("Cannot iterate over {}", type_name(&v)),
+                }
+            }
+            results
+        }
+        Filter::Pipe(left, right) => {
+            let mut results = Vec::new();
+            for value in eval(left, input)? {
+                results.extend(eval(right, &value)?);
+            }
+            results
+        }
+        Filter::Comma(left, right) => {
+            let mut results = eval(left, input)?;
+            results.extend(eval(right, input)?);
+            results
+        }
+        Filter::Compare(left, op, right) => {
+            let mut results = Vec::new();
+            for r in eval(right, input)? {
+                for l in eval(left, input)? {
+                    let ordering = compare(&l, &r);
+                    let matched = match op {
+                        CompareOp::Eq => ordering == Ordering::Equal,
+                        CompareOp::Ne => ordering != Ordering::Equal,
+                        CompareOp::Lt => ordering == Ordering::Less,
+                        CompareOp::Le => ordering != Ordering::Greater,
+                        CompareOp::Gt => ordering == Ordering::Greater,
+                        CompareOp::Ge => ordering != Ordering::Less,
+                    };
+                    results.push(Value::Bool(matched));
+                }
+            }
+            results
+        }
+        Filter::And(left, right) | Filter::Or(left, right) => {
+            let is_and = matches!(filter, Filter::And(..));
+            let mut results = Vec::new();
+            for l in eval(left, input)? {
+                if truthy(&l) != is_and {
+                    // `false and _` / `true or _` short-circuit
+                    results.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for r in eval(right, input)? {
+                    results.push(Value::Bool(truthy(&r)));
+                }
+            }
+            results
+        }
+        Filter::Select(cond) => {
+            if eval(cond, input)?.iter().any(truthy) {
+                vec![input.clone()]
+            } else {
+                vec![]
+            }
+        }
+        Filter::Not => vec![Value::Bool(!truthy(input))],
+        Filter::Length => {
+            let len = match input {
+                Value::Null => 0,
+                Value::String(s) => s.chars().count(),
+                Value::Array(items) => items.len(),
+                Value::Object(map) => map.len(),
+                Value::Number(n) => return Ok(vec![Value::from(n.as_f64().unwrap_or(0.0).abs())]),
+                v => anyhow::bail!("{} has no length", type_name(v)),
+            };
+            vec![Value::from(len)]
+        }
+        Filter::Keys => match input {
+            Value::Object(map) => vec![Value::from(
+                sorted_keys(map).into_iter().cloned().collect::<Vec<_>>(),
+            )],
+            Value::Array(items) => vec![Value::from((0..items.len()).collect::<Vec<_>>())],
+            v => anyhow::bail!("{} has no keys", type_name(v)),
+        },
+    };
+    Ok(results)
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// jq ordering: null < false < true < numbers < strings < arrays < objects
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(false) => 1,
+            Value::Bool(true) => 2,
+            Value::Number(_) => 3,
+            Value::String(_) => 4,
+            Value::Array(_) => 5,
+            Value::Object(_) => 6,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap_or(0.0), b.as_f64().unwrap_or(0.0));
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Array(a), Value::Array(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare(a, b))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| a.len().cmp(&b.len())),
+        (Value::Object(a), Value::Object(b)) => {
+            let (a_keys, b_keys) = (sorted_keys(a), sorted_keys(b));
+            a_keys.cmp(&b_keys).then_with(|| {
+                a_keys
+                    .iter()
+                    .map(|k| compare(&a[*k], &b[*k]))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn sorted_keys(map: &Map<String, Value>) -> Vec<&String> {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    keys
+}
+
+fn set_path(root: &mut Value, path: &str, new_value: Value) -> Result<()> {
+    let segments = parse_path(path)?;
+    let mut current = root;
+    for (i, segment) in segments.iter().enumerate() {
+        if current.is_null() {
+            *current = match segment {
+                PathSegment::Key(_) => Value::Object(Map::new()),
+                PathSegment::Index(_) => Value::Array(Vec::new()),
+            };
+        }
+        let slot = match (segment, current) {
+            (PathSegment::Key(key), Value::Object(map)) => {
+                map.entry(key.clone()).or_insert(Value::Null)
+            }
+            (PathSegment::Index(index), Value::Array(items)) => {
+                if *index == items.len() {
+                    items.push(Value::Null);
+                }
+                items
+                    .get_mut(*index)
+                    .ok_or_else(|| anyhow::anyhow!("{}: index {} is out of range", path, index))?
+            }
+            (_, v) => anyhow::bail!(
+                "{}: cannot go into {} at segment {}",
+                path,
+                type_name(v),
+                i + 1
+            ),
+        };
+        current = slot;
+    }
+    *current = new_value;
+    Ok(())
+}
+
+/// `server.port` or `servers[0].host`
+fn parse_path(path: &str) -> Result<Vec<PathSegment>> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        match key {
+            "" if rest.is_empty() => anyhow::bail!("Invalid path {}", path),
+            "" => {}
+            key => segments.push(PathSegment::Key(key.to_string())),
+        }
+        while let Some(inner) = rest.strip_prefix('[') {
+            let (index, tail) = inner
+                .split_once(']')
+                .ok_or_else(|| anyhow::anyhow!("{}: missing ]", path))?;
+            let index = index
+                .parse()
+                .map_err(|_| anyhow::anyhow!("{}: invalid index {}", path, index))?;
+            segments.push(PathSegment::Index(index));
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            anyhow::bail!("Invalid path {}", path);
+        }
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_query() -> Result<()> {
+        let data = json!({
+            "players": [
+                {"Name": "Wojciech Szczesny", "Position": "GK", "Kit Number": 1},
+                {"Name": "Kieran Tierney", "Position": "DF", "Kit Number": 3},
+                {"Name": "Alisson", "Position": "GK", "Kit Number": 13}
+            ]
+        });
+        let names = query(&data, r#".players[] | select(.Position=="GK") | .Name"#)?;
+        assert_eq!(names, [json!("Wojciech Szczesny"), json!("Alisson")]);
+
+        let names = query(
+            &data,
+            r#".players[] | select(.["Kit Number"] > 1 and (.Position == "GK" | not)) | .Name"#,
+        )?;
+        assert_eq!(names, [json!("Kieran Tierney")]);
+
+        assert_eq!(query(&data, ".players[-1].Name")?, [json!("Alisson")]);
+        assert_eq!(query(&data, ".players | length")?, [json!(3)]);
+        assert_eq!(query(&data, ".players[0] | keys")?[0][0], "Kit Number");
+        assert_eq!(query(&data, ".missing.deeper")?, [Value::Null]);
+        assert_eq!(query(&data, ".players[5], 1")?, [Value::Null, json!(1)]);
+        assert!(query(&data, ".players.Name").is_err());
+        assert!(query(&data, ".players[").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_path() -> Result<()> {
+        let mut value = json!({"server": {"port": 8080}, "hosts": ["a"]});
+        set_path(&mut value, "server.port", json!(9090))?;
+        set_path(&mut value, "hosts[1]", json!("b"))?;
+        set_path(&mut value, "db.replicas[0].host", json!("db1"))?;
+        assert_eq!(
+            value,
+            json!({
+                "server": {"port": 9090},
+                "hosts": ["a", "b"],
+                "db": {"replicas": [{"host": "db1"}]}
+            })
+        );
+        assert!(set_path(&mut value, "hosts[5]", json!("x")).is_err());
+        assert!(set_path(&mut value, "server.port.x", json!(1)).is_err());
+        assert!(parse_path("a..b").is_err());
+        Ok(())
+    }
+}
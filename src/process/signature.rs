@@ -0,0 +1,151 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::cli::TextSignFormat;
+
+/// First line of a detached signature file
+const SIGNATURE_FILE_HEADER: &str = "rcli signature v1";
+const METADATA_SIGNATURE_FIELD: &str = "metadata-signature";
+
+/// A detached signature with what is needed to check it, stored as `field: value` lines.
+///
+/// `signature` covers the data. `metadata_signature` covers every line before it, so the
+/// timestamp and comment cannot be changed without the key.
+#[derive(Debug, Clone)]
+pub struct SignatureFile {
+    pub algorithm: TextSignFormat,
+    /// Hex of the first 8 bytes of the SHA-256 of the public key
+    pub key_id: String,
+    /// Always set in rcli signature files, minisign ones may lack it
+    pub timestamp: Option<OffsetDateTime>,
+    pub comment: Option<String>,
+    pub signature: Vec<u8>,
+    pub metadata_signature: Vec<u8>,
+}
+
+impl SignatureFile {
+    pub fn new(
+        algorithm: TextSignFormat,
+        key_id: String,
+        comment: Option<String>,
+        signature: Vec<u8>,
+    ) -> Result<Self> {
+        if comment.as_deref().is_some_and(|c| c.contains(['\n', '\r'])) {
+            anyhow::bail!("Signature comments must be a single line");
+        }
+        let now = OffsetDateTime::now_utc();
+        Ok(Self {
+            algorithm,
+            key_id,
+            // RFC 3339 keeps whole seconds only once formatted
+            timestamp: Some(now.replace_nanosecond(0)?),
+            comment,
+            signature,
+            metadata_signature: Vec::new(),
+        })
+    }
+
+    /// The lines covered by `metadata_signature`
+    pub fn metadata(&self) -> Result<String> {
+        let mut out = format!(
+            "{}\nalgorithm: {}\nkey-id: {}\n",
+            SIGNATURE_FILE_HEADER, self.algorithm, self.key_id
+        );
+        if let Some(timestamp) = self.timestamp {
+            out.push_str(&format!("timestamp: {}\n", timestamp.format(&Rfc3339)?));
+        }
+        if let Some(comment) = &self.comment {
+            out.push_str(&format!("comment: {}\n", comment));
+        }
+        out.push_str(&format!(
+            "signature: {}\n",
+            URL_SAFE_NO_PAD.encode(&self.signature)
+        ));
+        Ok(out)
+    }
+
+    pub fn encode(&self) -> Result<String> {
+        Ok(format!(
+            "{}{}: {}\n",
+            self.metadata()?,
+            METADATA_SIGNATURE_FIELD,
+            URL_SAFE_NO_PAD.encode(&self.metadata_signature)
+        ))
+    }
+
+    pub fn decode(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some(SIGNATURE_FILE_HEADER) {
+            anyhow::bail!(
+                "Not an rcli signature file, expected it to start with '{}'",
+                SIGNATURE_FILE_HEADER
+            );
+        }
+
+        let (mut algorithm, mut key_id, mut timestamp, mut comment) = (None, None, None, None);
+        let (mut signature, mut metadata_signature) = (None, None);
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let (field, value) = line
+                .split_once(": ")
+                .ok_or_else(|| anyhow::anyhow!("Malformed signature file line: {}", line))?;
+            match field {
+                "algorithm" => algorithm = Some(value.parse()?),
+                "key-id" => key_id = Some(value.to_string()),
+                "timestamp" => timestamp = Some(OffsetDateTime::parse(value, &Rfc3339)?),
+                "comment" => comment = Some(value.to_string()),
+                "signature" => signature = Some(URL_SAFE_NO_PAD.decode(value)?),
+                METADATA_SIGNATURE_FIELD => {
+                    metadata_signature = Some(URL_SAFE_NO_PAD.decode(value)?)
+                }
+                v => anyhow::bail!("Unknown signature file field: {}", v),
+            }
+        }
+
+        let missing = |field: &str| anyhow::anyhow!("Signature file has no {} line", field);
+        Ok(Self {
+            algorithm: algorithm.ok_or_else(|| missing("algorithm"))?,
+            key_id: key_id.ok_or_else(|| missing("key-id"))?,
+            timestamp: Some(timestamp.ok_or_else(|| missing("timestamp"))?),
+            comment,
+            signature: signature.ok_or_else(|| missing("signature"))?,
+            metadata_signature: metadata_signature
+                .ok_or_else(|| missing(METADATA_SIGNATURE_FIELD))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_file_round_trip() -> Result<()> {
+        let mut file = SignatureFile::new(
+            TextSignFormat::Ed25519,
+            "0123456789abcdef".into(),
+            Some("release 1.0".into()),
+            vec![1, 2, 3],
+        )?;
+        file.metadata_signature = vec![4, 5, 6];
+        let text = file.encode()?;
+        assert!(
+            text.starts_with("rcli signature v1\nalgorithm: ed25519\nkey-id: 0123456789abcdef\n")
+        );
+
+        let decoded = SignatureFile::decode(&text)?;
+        assert_eq!(decoded.metadata()?, file.metadata()?);
+        assert_eq!(decoded.metadata_signature, vec![4, 5, 6]);
+
+        assert!(SignatureFile::decode("hello\n").is_err());
+        assert!(SignatureFile::decode(text.replace("key-id", "kid").as_str()).is_err());
+        let bad = SignatureFile::new(
+            TextSignFormat::Blake3,
+            "".into(),
+            Some("a\nb".into()),
+            vec![],
+        );
+        assert!(bad.is_err());
+        Ok(())
+    }
+}
@@ -1,4 +1,10 @@
-use std::{fs::File, io::Read};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, IsTerminal, Read, Write},
+    path::Path,
+};
+
+use rand::{rngs::OsRng, RngCore};
 
 pub fn get_reader(input: &str) -> anyhow::Result<Box<dyn Read>> {
     let reader: Box<dyn Read> = if input == "-" {
@@ -8,3 +14,267 @@ pub fn get_reader(input: &str) -> anyhow::Result<Box<dyn Read>> {
     };
     Ok(reader)
 }
+
+/// Buffered writer for `output`, or stdout when there is none
+pub fn get_writer(output: Option<&Path>) -> anyhow::Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = match output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(io::stdout())),
+    };
+    Ok(writer)
+}
+
+/// Number of bytes shown when binary output goes to a terminal
+const HEXDUMP_PREVIEW_LEN: usize = 256;
+
+/// Write decoded bytes to `output`, or to stdout as [`write_stdout`] does
+pub fn write_output(data: &[u8], output: Option<&Path>) -> anyhow::Result<()> {
+    if let Some(output) = output {
+        fs::write(output, data)?;
+        return Ok(());
+    }
+    write_stdout(&mut &data[..])
+}
+
+/// Copy `reader` to stdout: raw when piped. On a terminal text passes through,
+/// while binary data shows as a hexdump preview and the rest is left unread.
+pub fn write_stdout(reader: &mut dyn Read) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if !stdout.is_terminal() {
+        io::copy(reader, &mut stdout)?;
+        return Ok(());
+    }
+
+    let mut head = Vec::new();
+    reader
+        .take(HEXDUMP_PREVIEW_LEN as u64 + 1)
+        .read_to_end(&mut head)?;
+    // keep binary data from garbling the terminal
+    if looks_like_text(&head) {
+        stdout.write_all(&head)?;
+        io::copy(reader, &mut stdout)?;
+        writeln!(stdout)?;
+    } else {
+        write!(stdout, "{}", hexdump(&head, HEXDUMP_PREVIEW_LEN))?;
+        if head.len() > HEXDUMP_PREVIEW_LEN {
+            eprintln!(
+                "... more than {} bytes, use -o to save them",
+                HEXDUMP_PREVIEW_LEN
+            );
+        }
+    }
+    Ok(())
+}
+
+/// UTF-8 without control characters other than whitespace,
+/// allowing a character cut off at the end
+fn looks_like_text(data: &[u8]) -> bool {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&data[..e.valid_up_to()]).expect("valid up to here")
+        }
+        Err(_) => return false,
+    };
+    !text.contains(|c: char| c.is_control() && !c.is_whitespace())
+}
+
+/// `xxd`-style dump of at most `limit` bytes: offset, hex bytes and printable ASCII
+pub fn hexdump(data: &[u8], limit: usize) -> String {
+    let mut out = String::new();
+    for (i, chunk) in data[..data.len().min(limit)].chunks(16).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        out.push_str(&format!(
+            "{:08x}: {:<47}  {}\n",
+            i * 16,
+            hex.join(" "),
+            ascii
+        ));
+    }
+    out
+}
+
+const RANDOMART_WIDTH: usize = 17;
+const RANDOMART_HEIGHT: usize = 9;
+/// Marks by number of visits, then the start and end of the walk
+const RANDOMART_SYMBOLS: &[u8] = b" .o+=*BOX@%&#/^SE";
+
+/// OpenSSH's "drunken bishop" picture of a fingerprint, framed with `title` on top
+/// and `footer` below, so keys can be told apart at a glance
+pub fn randomart(title: &str, footer: &str, digest: &[u8]) -> String {
+    let max_visits = RANDOMART_SYMBOLS.len() - 3;
+    let mut field = [[0usize; RANDOMART_WIDTH]; RANDOMART_HEIGHT];
+    let (mut x, mut y) = (RANDOMART_WIDTH / 2, RANDOMART_HEIGHT / 2);
+    for byte in digest {
+        for step in 0..4 {
+            let bits = byte >> (2 * step);
+            x = if bits & 1 == 1 {
+                (x + 1).min(RANDOMART_WIDTH - 1)
+            } else {
+                x.saturating_sub(1)
+            };
+            y = if bits & 2 == 2 {
+                (y + 1).min(RANDOMART_HEIGHT - 1)
+            } else {
+                y.saturating_sub(1)
+            };
+            if field[y][x] < max_visits {
+                field[y][x] += 1;
+            }
+        }
+    }
+    field[RANDOMART_HEIGHT / 2][RANDOMART_WIDTH / 2] = RANDOMART_SYMBOLS.len() - 2;
+    field[y][x] = RANDOMART_SYMBOLS.len() - 1;
+
+    let border = |label: &str| {
+        let label = if label.len() > RANDOMART_WIDTH {
+            ""
+        } else {
+            label
+        };
+        let left = (RANDOMART_WIDTH - label.len()) / 2;
+        format!(
+            "+{}{}{}+\n",
+            "-".repeat(left),
+            label,
+            "-".repeat(RANDOMART_WIDTH - left - label.len())
+        )
+    };
+    let mut out = border(title);
+    for row in field {
+        let row: String = row.iter().map(|&n| RANDOMART_SYMBOLS[n] as char).collect();
+        out.push_str(&format!("|{}|\n", row));
+    }
+    out.push_str(&border(footer));
+    out
+}
+
+/// Write a secret to `path` with mode 0600, see [`write_atomic`]
+pub fn write_secret(path: impl AsRef<Path>, data: &[u8], force: bool) -> anyhow::Result<()> {
+    write_atomic(path, data, force, 0o600)
+}
+
+/// Write `data` to a temp file next to `path` and move it into place,
+/// so readers never see a partially written file.
+/// Refuses to replace an existing file unless `force` is set.
+pub fn write_atomic(
+    path: impl AsRef<Path>,
+    data: &[u8],
+    force: bool,
+    mode: u32,
+) -> anyhow::Result<()> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid output path: {}", path.display()))?
+        .to_string_lossy();
+    let tmp = path.with_file_name(format!(".{}.{:08x}.tmp", file_name, OsRng.next_u32()));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+
+    let ret = options.open(&tmp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    // a hard link fails if `path` exists, unlike a rename which replaces it
+    let ret = ret.and_then(|_| {
+        if force {
+            fs::rename(&tmp, path)
+        } else {
+            fs::hard_link(&tmp, path)
+        }
+    });
+    let _ = fs::remove_file(&tmp);
+    match ret {
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => anyhow::bail!(
+            "{} already exists, use --force to overwrite",
+            path.display()
+        ),
+        ret => Ok(ret?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_secret() -> anyhow::Result<()> {
+        let tmp = tempfile::tempdir()?;
+        let path = tmp.path().join("secret");
+
+        write_secret(&path, b"first", false)?;
+        assert!(write_secret(&path, b"second", false).is_err());
+        assert_eq!(fs::read(&path)?, b"first");
+        assert_eq!(fs::read_dir(tmp.path())?.count(), 1);
+
+        write_secret(&path, b"second", true)?;
+        assert_eq!(fs::read(&path)?, b"second");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_looks_like_text() {
+        assert!(looks_like_text(b"hello\r\n\tworld"));
+        assert!(looks_like_text(
+            "caf\u{e9}".as_bytes().split_last().unwrap().1
+        ));
+        assert!(!looks_like_text(b"\x89PNG\r\n\x1a\n"));
+        assert!(!looks_like_text(b"\xff\xfe"));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let dump = hexdump(b"\x89PNG\r\n\x1a\nrcli-rcli-rcli-rcli", 20);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("00000000: 89 50 4e 47 0d 0a 1a 0a"));
+        assert!(lines[0].ends_with(".PNG....rcli-rcl"));
+        assert!(lines[1].starts_with("00000010: 69 2d 72 63"));
+    }
+
+    #[test]
+    fn test_randomart_matches_ssh_keygen() -> anyhow::Result<()> {
+        use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+
+        // ssh-keygen -lv -f fixtures/ssh_ed25519.pub
+        let digest = STANDARD_NO_PAD.decode("gJCSyaHf80q+6heY63ZtGuSoH8OEBa6cF/G6iH0JCAk")?;
+        let expected = "\
++--[ED25519 256]--+
+|E+oo             |
+|B+..o.           |
+|+o.....          |
+|+=..o  .         |
+|+o+=+   S        |
+|.+==o+           |
+|..*o*o.          |
+| .o*ooo          |
+|.==+=+           |
++----[SHA256]-----+
+";
+        assert_eq!(randomart("[ED25519 256]", "[SHA256]", &digest), expected);
+        Ok(())
+    }
+}